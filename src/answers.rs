@@ -0,0 +1,200 @@
+use crate::{
+    calendar::Part,
+    error::{Location, ParseError},
+};
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs::{self, read_to_string},
+    io,
+    path::Path,
+};
+use toml::Spanned;
+
+pub const DEFAULT_PATH: &str = "answers.toml";
+
+/// Hash identifying a puzzle input: 64-bit FNV-1a, which is stable across
+/// platforms and compiler versions unlike the std hashers.
+pub fn input_hash(input: &str) -> String {
+    let hash = input.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Accepted answers, keyed by day, part and input hash.
+///
+/// Stored as TOML, one table per day and part:
+///
+/// ```toml
+/// [day01.part1]
+/// 9f3b5c4e2a1d0f87 = "11"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct KnownAnswers {
+    answers: BTreeMap<(u8, u8, String), String>,
+}
+
+impl KnownAnswers {
+    /// Load the answers from `path`, starting empty if the file does not exist yet.
+    pub fn load(path: &Path) -> Result<KnownAnswers, String> {
+        match read_to_string(path) {
+            Ok(content) => KnownAnswers::parse(&content)
+                .map_err(|error| error.in_file(&path.display().to_string()).to_string()),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(KnownAnswers::default()),
+            Err(error) => Err(format!("{}: {}", path.display(), error)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_toml())
+    }
+
+    pub fn get(&self, day: u8, part: Part, hash: &str) -> Option<&str> {
+        self.answers
+            .get(&(day, part_number(part), hash.to_string()))
+            .map(String::as_str)
+    }
+
+    pub fn insert(&mut self, day: u8, part: Part, hash: &str, answer: &str) {
+        self.answers.insert(
+            (day, part_number(part), hash.to_string()),
+            answer.to_string(),
+        );
+    }
+
+    pub fn parse(content: &str) -> Result<KnownAnswers, ParseError> {
+        let tables: Tables = toml::from_str(content).map_err(|error| ParseError::Syntax {
+            message: error.message().to_string(),
+            at: location(content, error.span().map_or(0, |span| span.start)),
+        })?;
+        let unexpected = |key: &Spanned<String>| ParseError::UnexpectedToken {
+            token: key.get_ref().clone(),
+            at: location(content, key.span().start),
+        };
+        let mut known_answers = KnownAnswers::default();
+        for (day_key, parts) in tables {
+            let day = day_key
+                .get_ref()
+                .strip_prefix("day")
+                .and_then(|day| day.parse().ok())
+                .ok_or_else(|| unexpected(&day_key))?;
+            for (part_key, answers) in parts {
+                let part = match part_key
+                    .get_ref()
+                    .strip_prefix("part")
+                    .and_then(|part| part.parse().ok())
+                {
+                    Some(part @ 1..=2) => part,
+                    _ => return Err(unexpected(&part_key)),
+                };
+                for (hash, answer) in answers {
+                    known_answers.answers.insert((day, part, hash), answer);
+                }
+            }
+        }
+        Ok(known_answers)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut toml =
+            "# Accepted answers, keyed by day, part and FNV-1a hash of the input\n".to_string();
+        let mut table = None;
+        for ((day, part, hash), answer) in &self.answers {
+            if table != Some((day, part)) {
+                table = Some((day, part));
+                write!(toml, "\n[day{:02}.part{}]\n", day, part).unwrap();
+            }
+            writeln!(toml, "{} = {}", hash, quote(answer)).unwrap();
+        }
+        toml
+    }
+}
+
+fn part_number(part: Part) -> u8 {
+    match part {
+        Part::One => 1,
+        Part::Two => 2,
+    }
+}
+
+/// Answers as stored, by `dayNN` and `partN` tables and then by input hash
+type Tables = BTreeMap<Spanned<String>, BTreeMap<Spanned<String>, BTreeMap<String, String>>>;
+
+/// Location of the byte `offset` in `content`
+fn location(content: &str, offset: usize) -> Location {
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    Location::new(
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', r"\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_hash() {
+        assert_eq!(input_hash(""), "cbf29ce484222325");
+        assert_eq!(input_hash("a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut known_answers = KnownAnswers::default();
+        known_answers.insert(2, Part::Two, "00ff", "4");
+        known_answers.insert(1, Part::One, "00ff", "11");
+        known_answers.insert(1, Part::One, "abcd", "say \"hi\"");
+        let toml = known_answers.to_toml();
+        assert_eq!(
+            toml,
+            r#"# Accepted answers, keyed by day, part and FNV-1a hash of the input
+
+[day01.part1]
+00ff = "11"
+abcd = "say \"hi\""
+
+[day02.part2]
+00ff = "4"
+"#
+        );
+        assert_eq!(KnownAnswers::parse(&toml), Ok(known_answers));
+    }
+
+    #[test]
+    fn test_parse_comments() {
+        let toml = "# accepted answers\n\
+                    [day01.part1] # first star\n\
+                    1f83 = \"1320851\" # accepted 2024-12-01\n\
+                    \"00ff\" = '11'\n";
+        let mut known_answers = KnownAnswers::default();
+        known_answers.insert(1, Part::One, "1f83", "1320851");
+        known_answers.insert(1, Part::One, "00ff", "11");
+        assert_eq!(KnownAnswers::parse(toml), Ok(known_answers));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            KnownAnswers::parse("[day01.part3]\n"),
+            Err(ParseError::UnexpectedToken {
+                token: "part3".to_string(),
+                at: Location::new(1, 8),
+            })
+        );
+        assert_eq!(
+            KnownAnswers::parse("[day01.part1]\n00ff = 1\n")
+                .unwrap_err()
+                .location(),
+            &Location::new(2, 8)
+        );
+        assert!(KnownAnswers::parse("00ff = \"1\"\n").is_err());
+        assert!(KnownAnswers::parse("[day01.part1\n").is_err());
+    }
+}
@@ -0,0 +1,254 @@
+use crate::calendar::{Part, Timings};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    path::Path,
+    str::FromStr,
+    time::Duration,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Phase {
+    Parse,
+    Part(Part),
+}
+
+impl Phase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Parse => "parse",
+            Phase::Part(Part::One) => "part1",
+            Phase::Part(Part::Two) => "part2",
+        }
+    }
+}
+
+impl FromStr for Phase {
+    type Err = String;
+
+    fn from_str(phase: &str) -> Result<Phase, String> {
+        match phase {
+            "parse" => Ok(Phase::Parse),
+            "part1" => Ok(Phase::Part(Part::One)),
+            "part2" => Ok(Phase::Part(Part::Two)),
+            _ => Err(format!("Invalid phase: {}", phase)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub p95: Duration,
+}
+
+impl Stats {
+    /// Summarise the durations of repeated runs, using the nearest-rank
+    /// percentile, or `None` without any run.
+    pub fn from_durations(durations: &[Duration]) -> Option<Stats> {
+        let mut sorted = durations.to_vec();
+        sorted.sort();
+        let percentile = |p: usize| sorted[(sorted.len() * p).div_ceil(100).max(1) - 1];
+        Some(Stats {
+            min: *sorted.first()?,
+            median: percentile(50),
+            p95: percentile(95),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Measurement {
+    pub day: u8,
+    pub phase: Phase,
+    pub stats: Stats,
+}
+
+pub type Baseline = BTreeMap<(u8, Phase), Stats>;
+
+/// Summarise the timings of one day, keeping parsing and the selected
+/// parts, and skipping phases that were never run.
+pub fn measure(day: u8, timings: &Timings, parts: &[Part]) -> Vec<Measurement> {
+    let mut phases = vec![(Phase::Parse, &timings.parse)];
+    for &part in parts {
+        match part {
+            Part::One => phases.push((Phase::Part(part), &timings.part_one)),
+            Part::Two => phases.push((Phase::Part(part), &timings.part_two)),
+        }
+    }
+    phases
+        .into_iter()
+        .filter_map(|(phase, durations)| {
+            Some(Measurement {
+                day,
+                phase,
+                stats: Stats::from_durations(durations)?,
+            })
+        })
+        .collect()
+}
+
+pub fn save_baseline(path: &Path, measurements: &[Measurement]) -> Result<(), String> {
+    let error = |error: csv::Error| format!("{}: {}", path.display(), error);
+    let mut writer = csv::Writer::from_path(path).map_err(error)?;
+    writer
+        .write_record(["day", "phase", "min_ns", "median_ns", "p95_ns"])
+        .map_err(error)?;
+    for measurement in measurements {
+        writer
+            .write_record([
+                measurement.day.to_string(),
+                measurement.phase.name().to_string(),
+                measurement.stats.min.as_nanos().to_string(),
+                measurement.stats.median.as_nanos().to_string(),
+                measurement.stats.p95.as_nanos().to_string(),
+            ])
+            .map_err(error)?;
+    }
+    writer
+        .flush()
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+pub fn load_baseline(path: &Path) -> Result<Baseline, String> {
+    let error = |error: String| format!("{}: {}", path.display(), error);
+    let mut reader = csv::Reader::from_path(path).map_err(|e| error(e.to_string()))?;
+    let mut baseline = Baseline::new();
+    for (row_idx, row) in reader.records().enumerate() {
+        let row = row.map_err(|e| error(e.to_string()))?;
+        let field = |idx: usize| {
+            row.get(idx)
+                .ok_or_else(|| error(format!("line {}: missing column {}", row_idx + 2, idx + 1)))
+        };
+        let nanos = |idx: usize| -> Result<Duration, String> {
+            field(idx)?
+                .parse()
+                .map(Duration::from_nanos)
+                .map_err(|_| error(format!("line {}: invalid duration", row_idx + 2)))
+        };
+        let day = field(0)?
+            .parse()
+            .map_err(|_| error(format!("line {}: invalid day", row_idx + 2)))?;
+        let phase = field(1)?.parse().map_err(error)?;
+        let stats = Stats {
+            min: nanos(2)?,
+            median: nanos(3)?,
+            p95: nanos(4)?,
+        };
+        baseline.insert((day, phase), stats);
+    }
+    Ok(baseline)
+}
+
+/// Whether `stats` has a median more than `threshold` percent slower than `baseline`.
+pub fn is_regression(stats: &Stats, baseline: &Stats, threshold: f64) -> bool {
+    stats.median.as_secs_f64() > baseline.median.as_secs_f64() * (1.0 + threshold / 100.0)
+}
+
+/// Print one row per measurement, compared against the baseline if given,
+/// and return the number of regressions.
+pub fn write_table(
+    out: &mut impl Write,
+    measurements: &[Measurement],
+    baseline: Option<&Baseline>,
+    threshold: f64,
+) -> io::Result<usize> {
+    write!(
+        out,
+        "{:>3}  {:<6} {:>12} {:>12} {:>12}",
+        "day", "phase", "min", "median", "p95"
+    )?;
+    if baseline.is_some() {
+        write!(out, " {:>12} {:>8}", "baseline", "change")?;
+    }
+    writeln!(out)?;
+    let mut regressions = 0;
+    for measurement in measurements {
+        let stats = &measurement.stats;
+        write!(
+            out,
+            "{:>3}  {:<6} {:>12.3?} {:>12.3?} {:>12.3?}",
+            measurement.day,
+            measurement.phase.name(),
+            stats.min,
+            stats.median,
+            stats.p95
+        )?;
+        if let Some(baseline) = baseline {
+            match baseline.get(&(measurement.day, measurement.phase)) {
+                Some(previous) => {
+                    let change =
+                        (stats.median.as_secs_f64() / previous.median.as_secs_f64() - 1.0) * 100.0;
+                    write!(out, " {:>12.3?} {:>+7.1}%", previous.median, change)?;
+                    if is_regression(stats, previous, threshold) {
+                        regressions += 1;
+                        write!(out, "  regression")?;
+                    }
+                }
+                None => write!(out, " {:>12} {:>8}", "-", "new")?,
+            }
+        }
+        writeln!(out)?;
+    }
+    Ok(regressions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|&ms| Duration::from_millis(ms)).collect()
+    }
+
+    #[test]
+    fn test_stats_from_durations() {
+        let stats = Stats::from_durations(&millis(&(1..=20).rev().collect::<Vec<_>>())).unwrap();
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.median, Duration::from_millis(10));
+        assert_eq!(stats.p95, Duration::from_millis(19));
+
+        let stats = Stats::from_durations(&millis(&[7])).unwrap();
+        let seven = Duration::from_millis(7);
+        assert_eq!((stats.min, stats.median, stats.p95), (seven, seven, seven));
+
+        assert_eq!(Stats::from_durations(&[]), None);
+        assert!(measure(1, &Timings::default(), &Part::BOTH).is_empty());
+    }
+
+    #[test]
+    fn test_is_regression() {
+        let stats = |median| Stats {
+            min: Duration::ZERO,
+            median: Duration::from_millis(median),
+            p95: Duration::ZERO,
+        };
+        assert!(!is_regression(&stats(105), &stats(100), 10.0));
+        assert!(is_regression(&stats(115), &stats(100), 10.0));
+        assert!(!is_regression(&stats(50), &stats(100), 0.0));
+    }
+
+    #[test]
+    fn test_baseline_roundtrip() {
+        let path = std::env::temp_dir().join(format!("aoc_baseline_{}.csv", std::process::id()));
+        let measurements = measure(
+            3,
+            &Timings {
+                parse: millis(&[1, 2, 3]),
+                part_one: millis(&[4]),
+                part_two: millis(&[5, 6]),
+            },
+            &[Part::Two],
+        );
+        save_baseline(&path, &measurements).unwrap();
+        let baseline = load_baseline(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(baseline.len(), 2);
+        assert_eq!(
+            baseline[&(3, Phase::Part(Part::Two))].median,
+            Duration::from_millis(5)
+        );
+        assert_eq!(baseline[&(3, Phase::Parse)].p95, Duration::from_millis(3));
+    }
+}
@@ -1,8 +1,17 @@
-use std::collections::{HashMap, HashSet};
-use std::fs::read_to_string;
-fn calculate_distance(vec1: &Vec<i32>, vec2: &Vec<i32>) -> i32 {
-    let mut vec1 = vec1.clone();
-    let mut vec2 = vec2.clone();
+use super::{Answers, Options, Part, Solution};
+use crate::{
+    error::{parse_number, Location, ParseError},
+    utils::for_each_line,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    io::BufRead,
+};
+
+/// Sum of the distances between the smallest, second smallest, ... ids of both lists.
+pub fn calculate_distance(vec1: &[i32], vec2: &[i32]) -> i32 {
+    let mut vec1 = vec1.to_vec();
+    let mut vec2 = vec2.to_vec();
     vec1.sort();
     vec2.sort();
     vec1.iter()
@@ -11,60 +20,87 @@ fn calculate_distance(vec1: &Vec<i32>, vec2: &Vec<i32>) -> i32 {
         .sum()
 }
 
-fn calculate_similarity(vec1: &Vec<i32>, vec2: &Vec<i32>) -> i32 {
-    let unique_vec1: HashSet<i32> = vec1.clone().into_iter().collect();
+/// Sum of each id of the first list times its number of occurrences in the second list.
+pub fn calculate_similarity(vec1: &[i32], vec2: &[i32]) -> i32 {
+    let unique_vec1: HashSet<i32> = vec1.iter().copied().collect();
     let mut frequency_map: HashMap<i32, i32> = HashMap::new();
 
     for i in vec2 {
-        if unique_vec1.contains(&i) {
+        if unique_vec1.contains(i) {
             *frequency_map.entry(*i).or_insert(0) += 1;
         }
     }
     let mut running_sum = 0;
     for i in vec1 {
-        if frequency_map.contains_key(&i) {
-            let key = *i;
-            let value = *frequency_map.get(&i).unwrap();
-            running_sum += key * value;
+        if let Some(value) = frequency_map.get(i) {
+            running_sum += i * value;
         }
     }
     running_sum
 }
 
-pub fn day01() {
-    print!("       .--'~ ~ ~|        .-' *       \\  /     '-.  1: ");
-    let file = read_to_string("src/data/day01.tsv").expect("Unable to open file");
+/// Read both location lists line by line, holding only the numbers in memory.
+pub fn parse_reader(reader: impl BufRead) -> Result<(Vec<i32>, Vec<i32>), ParseError> {
     let mut vec1: Vec<i32> = Vec::new();
     let mut vec2: Vec<i32> = Vec::new();
-
-    for line in file.lines() {
+    for_each_line(reader, |line_idx, line| {
         let mut iter = line.split_whitespace();
-        vec1.push(
-            iter.next()
-                .unwrap()
-                .parse()
-                .expect("Unable to parse number"),
-        );
-        vec2.push(
-            iter.next()
-                .unwrap()
-                .parse()
-                .expect("Unable to parse number"),
-        );
-    }
+        for (vec, expected) in [(&mut vec1, "left location"), (&mut vec2, "right location")] {
+            let token = iter.next().ok_or(ParseError::Missing {
+                expected,
+                at: Location::new(line_idx + 1, line.chars().count() + 1),
+            })?;
+            vec.push(parse_number(line_idx, line, token)?);
+        }
+        if let Some(token) = iter.next() {
+            return Err(ParseError::UnexpectedToken {
+                token: token.to_string(),
+                at: Location::of_token(line_idx, line, token),
+            });
+        }
+        Ok(())
+    })?;
+    Ok((vec1, vec2))
+}
+
+/// Answer the requested parts straight from a reader.
+pub fn solve_stream(
+    reader: &mut dyn BufRead,
+    parts: &[Part],
+    _: &Options,
+) -> Result<Answers, ParseError> {
+    Ok(super::answer::<Day01>(&parse_reader(reader)?, parts))
+}
+
+pub struct Day01;
+
+impl Solution for Day01 {
+    const DAY: u8 = 1;
+    const ART: &'static str = r"       .--'~ ~ ~|        .-' *       \  /     '-. ";
+    const INPUT: &'static str = "src/data/day01.tsv";
+    const LABELS: [&'static str; 2] = ["Location pair distance", "similarity"];
 
-    let distance = calculate_distance(&vec1, &vec2);
+    type Input = (Vec<i32>, Vec<i32>);
+    type PartOne = i32;
+    type PartTwo = i32;
 
-    print!("Location pair distance: {}, ", distance);
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        parse_reader(input.as_bytes())
+    }
 
-    let similarity = calculate_similarity(&vec1, &vec2);
+    fn part_one((vec1, vec2): &Self::Input) -> i32 {
+        calculate_distance(vec1, vec2)
+    }
 
-    println!("similarity: {}", similarity);
+    fn part_two((vec1, vec2): &Self::Input) -> i32 {
+        calculate_similarity(vec1, vec2)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::io;
 
     fn get_input_vectors() -> (Vec<i32>, Vec<i32>) {
         (vec![3, 4, 2, 1, 3, 3], vec![4, 3, 5, 3, 9, 3])
@@ -81,4 +117,34 @@ mod tests {
         let (vec1, vec2) = get_input_vectors();
         assert_eq!(calculate_similarity(&vec1, &vec2), 31);
     }
+
+    #[test]
+    fn test_parse_reader() {
+        let input = "3   4\r\n4   3\r\n2   5\r\n";
+        assert_eq!(
+            parse_reader(io::BufReader::with_capacity(4, input.as_bytes())),
+            Ok((vec![3, 4, 2], vec![4, 3, 5]))
+        );
+        let answers =
+            solve_stream(&mut input.as_bytes(), &[Part::Two], &Options::default()).unwrap();
+        assert_eq!(answers[0].value, "7");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Day01::parse("3   4\n4   x3\n"),
+            Err(ParseError::InvalidNumber {
+                token: "x3".to_string(),
+                at: Location::new(2, 5),
+            })
+        );
+        assert_eq!(
+            Day01::parse("3   4\n4\n"),
+            Err(ParseError::Missing {
+                expected: "right location",
+                at: Location::new(2, 2),
+            })
+        );
+    }
 }
@@ -1,69 +1,347 @@
-use rayon::prelude::*;
-use std::{
-    fs::read_to_string,
-    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+use super::{Answer, Answers, Options, Part, Solution};
+use crate::{
+    error::{parse_number, ParseError},
+    utils::for_each_line,
 };
+use rayon::prelude::*;
+use std::{fmt, io::BufRead, time::Instant};
 
-// TODO: Change brute force solution to a more efficient one.
-fn check_report_safety_with_problem_dampener(report: &Vec<i32>) -> bool {
-    let safe_with_dampener = AtomicBool::new(false);
-    report
-        .clone()
-        .par_iter()
-        .enumerate()
-        .for_each(|(j, _level)| {
-            if !safe_with_dampener.load(Ordering::Relaxed) {
-                let mut report_permutation = report.clone();
-                report_permutation.remove(j);
-                if check_report_safety(&report_permutation) {
-                    safe_with_dampener.store(true, Ordering::Relaxed);
-                };
-            }
-        });
-    safe_with_dampener.load(Ordering::Relaxed)
+/// Directions in which the levels of a safe report may move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trend {
+    Increasing,
+    Decreasing,
+    Either,
+}
+
+impl Trend {
+    /// Whether each allowed direction is increasing
+    fn directions(self) -> &'static [bool] {
+        match self {
+            Trend::Increasing => &[true],
+            Trend::Decreasing => &[false],
+            Trend::Either => &[true, false],
+        }
+    }
+}
+
+/// Rules a report has to follow to count as safe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SafetyPolicy {
+    /// Smallest allowed difference between adjacent levels
+    pub min_step: i32,
+    /// Largest allowed difference between adjacent levels
+    pub max_step: i32,
+    pub trend: Trend,
+    /// Number of levels that may be removed to make a report safe
+    pub removals: usize,
+}
+
+impl SafetyPolicy {
+    /// Rules of the first part: steps of 1 to 3 in one direction, nothing removed
+    pub const STRICT: SafetyPolicy = SafetyPolicy {
+        min_step: 1,
+        max_step: 3,
+        trend: Trend::Either,
+        removals: 0,
+    };
+    /// Rules of the second part, with the Problem Dampener removing one level
+    pub const DAMPENED: SafetyPolicy = SafetyPolicy {
+        removals: 1,
+        ..SafetyPolicy::STRICT
+    };
+
+    fn is_safe_step(&self, from: i32, to: i32, increasing: bool) -> bool {
+        let step = if increasing { to - from } else { from - to };
+        (self.min_step..=self.max_step).contains(&step)
+    }
+
+    /// Whether the report is safe after removing at most `removals` levels.
+    ///
+    /// Up to one removal this is linear, as only the two levels of the first
+    /// unsafe step are candidates for removal. More removals fall back to
+    /// [`SafetyPolicy::min_removals`].
+    pub fn is_safe(&self, report: &[i32]) -> bool {
+        match self.removals {
+            0 => self.trend.directions().iter().any(|&increasing| {
+                report
+                    .windows(2)
+                    .all(|pair| self.is_safe_step(pair[0], pair[1], increasing))
+            }),
+            1 => self.trend.directions().iter().any(|&increasing| {
+                self.first_unsafe_step(report, increasing).is_none()
+                    || self.fixing_removal(report, increasing).is_some()
+            }),
+            removals => self.min_removals(report) <= removals,
+        }
+    }
+
+    /// Index of the first pair of adjacent levels not allowed in the given direction
+    fn first_unsafe_step(&self, report: &[i32], increasing: bool) -> Option<usize> {
+        report
+            .windows(2)
+            .position(|pair| !self.is_safe_step(pair[0], pair[1], increasing))
+    }
+
+    /// Index of a level whose removal makes an unsafe report safe in the given direction.
+    ///
+    /// The first unsafe step of a report can only be fixed by removing one of
+    /// its two levels, so only those two need to be tried.
+    fn fixing_removal(&self, report: &[i32], increasing: bool) -> Option<usize> {
+        let idx = self.first_unsafe_step(report, increasing)?;
+        [idx, idx + 1]
+            .into_iter()
+            .find(|&skip| self.is_safe_without(report, skip, increasing))
+    }
+
+    /// Whether the report is safe in the given direction once the level at `skip` is removed.
+    fn is_safe_without(&self, report: &[i32], skip: usize, increasing: bool) -> bool {
+        let mut levels = report
+            .iter()
+            .enumerate()
+            .filter(|&(idx, _)| idx != skip)
+            .map(|(_, &level)| level);
+        let Some(mut previous) = levels.next() else {
+            return true;
+        };
+        levels.all(|level| {
+            let safe = self.is_safe_step(previous, level, increasing);
+            previous = level;
+            safe
+        })
+    }
+
+    /// Smallest number of levels to remove for the report to be safe,
+    /// ignoring the allowed `removals`.
+    ///
+    /// This is the report length minus the longest chain of levels in which
+    /// every kept level follows the previous kept one by an allowed step,
+    /// found by dynamic programming in quadratic time.
+    pub fn min_removals(&self, report: &[i32]) -> usize {
+        let longest_chain = self
+            .trend
+            .directions()
+            .iter()
+            .map(|&increasing| {
+                let mut chain_ending_at: Vec<usize> = Vec::with_capacity(report.len());
+                for (idx, &level) in report.iter().enumerate() {
+                    let longest_before = (0..idx)
+                        .filter(|&before| self.is_safe_step(report[before], level, increasing))
+                        .map(|before| chain_ending_at[before])
+                        .max()
+                        .unwrap_or(0);
+                    chain_ending_at.push(longest_before + 1);
+                }
+                chain_ending_at.into_iter().max().unwrap_or(0)
+            })
+            .max()
+            .unwrap_or(0);
+        report.len() - longest_chain
+    }
+
+    /// Explain why the report is unsafe without removals, or `None` if it is safe.
+    ///
+    /// The direction of the report is taken from its first step that changes
+    /// the level, unless the policy only allows one direction.
+    pub fn diagnose(&self, report: &[i32]) -> Option<Diagnosis> {
+        let increasing = match self.trend {
+            Trend::Increasing => true,
+            Trend::Decreasing => false,
+            Trend::Either => report
+                .windows(2)
+                .find(|pair| pair[0] != pair[1])
+                .is_none_or(|pair| pair[0] < pair[1]),
+        };
+        let idx = self.first_unsafe_step(report, increasing)?;
+        let step = if increasing {
+            report[idx + 1] - report[idx]
+        } else {
+            report[idx] - report[idx + 1]
+        };
+        let violation = if step < 0 {
+            Violation::DirectionChange
+        } else if step < self.min_step {
+            Violation::StepTooSmall
+        } else {
+            Violation::StepTooLarge
+        };
+        let fix = self
+            .trend
+            .directions()
+            .iter()
+            .filter_map(|&increasing| self.fixing_removal(report, increasing))
+            .min();
+        Some(Diagnosis {
+            offending: idx + 1,
+            violation,
+            fix,
+        })
+    }
+}
+
+/// Way in which a step between two levels breaks the [`SafetyPolicy`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+    DirectionChange,
+    StepTooSmall,
+    StepTooLarge,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::DirectionChange => write!(f, "direction change"),
+            Violation::StepTooSmall => write!(f, "step too small"),
+            Violation::StepTooLarge => write!(f, "step too large"),
+        }
+    }
 }
 
-fn check_report_safety(report: &Vec<i32>) -> bool {
-    return (report.iter().is_sorted_by(|a, b| a < b) || report.iter().is_sorted_by(|a, b| a > b))
-        && !(report.windows(2).any(|pair| (pair[1] - pair[0]).abs() > 3));
+/// Why a report is unsafe and how it could be fixed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Diagnosis {
+    /// Index of the first level breaking the policy
+    pub offending: usize,
+    pub violation: Violation,
+    /// Index of a level whose removal makes the report safe, if there is one
+    pub fix: Option<usize>,
 }
 
-pub fn day02() {
-    print!("    .--'~  ,* ~ |        |  >o<   \\_\\_\\|_/__/   |  2: ");
-    let file = read_to_string("src/data/day02.tsv").expect("Unable to open file");
-
-    let safe_report_count_without_problem_dampener = AtomicU64::new(0);
-    let safe_report_count_with_problem_dampener = AtomicU64::new(0);
-    file.lines()
-        .collect::<Vec<_>>()
-        .par_iter()
-        .for_each(|line| {
-            let report_iter = line.split_whitespace();
-            let report: Vec<i32> = report_iter
-                .map(|s| s.parse().expect("Unable to parse number"))
-                .collect();
-            if check_report_safety(&report) {
-                safe_report_count_without_problem_dampener.fetch_add(1, Ordering::Relaxed);
-                safe_report_count_with_problem_dampener.fetch_add(1, Ordering::Relaxed);
-            } else {
-                if check_report_safety_with_problem_dampener(&report) {
-                    safe_report_count_with_problem_dampener.fetch_add(1, Ordering::Relaxed);
+impl Diagnosis {
+    /// The report with its offending level in brackets, followed by the
+    /// violation and the fix, e.g. `1 3 [2] 4 5: direction change, safe without level 2`.
+    ///
+    /// Levels are numbered from 1 here, unlike the indices of the diagnosis.
+    pub fn annotate(&self, report: &[i32]) -> String {
+        let levels: Vec<String> = report
+            .iter()
+            .enumerate()
+            .map(|(idx, level)| {
+                if idx == self.offending {
+                    format!("[{}]", level)
+                } else {
+                    level.to_string()
                 }
+            })
+            .collect();
+        let fix = match self.fix {
+            Some(idx) => format!("safe without level {} ({})", idx + 1, report[idx]),
+            None => "not fixable by removing one level".to_string(),
+        };
+        format!("{}: {}, {}", levels.join(" "), self.violation, fix)
+    }
+}
+
+/// Whether the report is safe after removing at most one level.
+pub fn check_report_safety_with_problem_dampener(report: &[i32]) -> bool {
+    SafetyPolicy::DAMPENED.is_safe(report)
+}
+
+/// Whether the levels strictly increase or decrease, by at most 3 per step.
+pub fn check_report_safety(report: &[i32]) -> bool {
+    SafetyPolicy::STRICT.is_safe(report)
+}
+
+/// Number of reports safe under each of the policies, counted while
+/// reading the reports line by line so that only one is held in memory.
+/// Blank lines hold no report and are skipped.
+pub fn count_safe_reports(
+    reader: impl BufRead,
+    policies: &[SafetyPolicy],
+) -> Result<Vec<u64>, ParseError> {
+    let mut counts = vec![0; policies.len()];
+    let mut report = vec![];
+    for_each_line(reader, |line_idx, line| {
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        report.clear();
+        for token in line.split_whitespace() {
+            report.push(parse_number(line_idx, line, token)?);
+        }
+        for (count, policy) in counts.iter_mut().zip(policies) {
+            if policy.is_safe(&report) {
+                *count += 1;
             }
-        });
-    print!(
-        "Safe reports: {:?}, ",
-        safe_report_count_without_problem_dampener
-    );
-    println!(
-        "safe reports with problem dampener: {:?}",
-        safe_report_count_with_problem_dampener
-    );
+        }
+        Ok(())
+    })?;
+    Ok(counts)
+}
+
+/// Answer the requested parts in a single pass over a reader.
+///
+/// Both parts are counted together, so each answer carries the duration of the whole pass.
+pub fn solve_stream(
+    reader: &mut dyn BufRead,
+    parts: &[Part],
+    _: &Options,
+) -> Result<Answers, ParseError> {
+    let start = Instant::now();
+    let policies: Vec<SafetyPolicy> = parts
+        .iter()
+        .map(|part| match part {
+            Part::One => SafetyPolicy::STRICT,
+            Part::Two => SafetyPolicy::DAMPENED,
+        })
+        .collect();
+    let counts = count_safe_reports(reader, &policies)?;
+    let duration = start.elapsed();
+    Ok(parts
+        .iter()
+        .zip(counts)
+        .map(|(&part, count)| Answer {
+            part,
+            value: count.to_string(),
+            duration,
+        })
+        .collect())
+}
+
+pub struct Day02;
+
+impl Solution for Day02 {
+    const DAY: u8 = 2;
+    const ART: &'static str = r"    .--'~  ,* ~ |        |  >o<   \_\_\|_/__/   | ";
+    const INPUT: &'static str = "src/data/day02.tsv";
+    const LABELS: [&'static str; 2] = ["Safe reports", "safe reports with problem dampener"];
+
+    type Input = Vec<Vec<i32>>;
+    type PartOne = u64;
+    type PartTwo = u64;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        input
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(line_idx, line)| {
+                line.split_whitespace()
+                    .map(|s| parse_number(line_idx, line, s))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn part_one(reports: &Self::Input) -> u64 {
+        reports
+            .par_iter()
+            .filter(|report| check_report_safety(report))
+            .count() as u64
+    }
+
+    fn part_two(reports: &Self::Input) -> u64 {
+        reports
+            .par_iter()
+            .filter(|report| check_report_safety_with_problem_dampener(report))
+            .count() as u64
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::rng::XorShift;
 
     fn get_reports() -> Vec<Vec<i32>> {
         vec![
@@ -89,7 +367,7 @@ mod tests {
         let reports = get_reports();
         let report_validities = get_report_validities_without_problem_dampener();
         for (report, validity) in reports.iter().zip(report_validities) {
-            assert_eq!(check_report_safety(&report), validity);
+            assert_eq!(check_report_safety(report), validity);
         }
     }
 
@@ -98,7 +376,170 @@ mod tests {
         let reports = get_reports();
         let report_validities = get_report_validities_with_problem_dampener();
         for (report, validity) in reports.iter().zip(report_validities) {
-            assert_eq!(check_report_safety_with_problem_dampener(&report), validity);
+            assert_eq!(check_report_safety_with_problem_dampener(report), validity);
+        }
+    }
+
+    #[test]
+    fn test_count_safe_reports() {
+        let input: String = get_reports()
+            .iter()
+            .map(|report| {
+                format!(
+                    "{}\n",
+                    report
+                        .iter()
+                        .map(i32::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                )
+            })
+            .collect();
+        assert_eq!(
+            count_safe_reports(
+                input.as_bytes(),
+                &[SafetyPolicy::STRICT, SafetyPolicy::DAMPENED]
+            ),
+            Ok(vec![2, 4])
+        );
+        assert!(count_safe_reports("1 2\n3 x\n".as_bytes(), &[SafetyPolicy::STRICT]).is_err());
+        let answers = solve_stream(
+            &mut input.as_bytes(),
+            &[Part::Two, Part::One],
+            &Options::default(),
+        )
+        .unwrap();
+        let values: Vec<&str> = answers.iter().map(|answer| answer.value.as_str()).collect();
+        assert_eq!(values, ["4", "2"]);
+    }
+
+    #[test]
+    fn test_blank_lines_are_skipped() {
+        let input = "7 6 4 2 1\n\n1 2 7 8 9\n  \n";
+        assert_eq!(
+            count_safe_reports(input.as_bytes(), &[SafetyPolicy::STRICT]),
+            Ok(vec![1])
+        );
+        let reports = Day02::parse(input).unwrap();
+        assert_eq!(reports, vec![vec![7, 6, 4, 2, 1], vec![1, 2, 7, 8, 9]]);
+        assert_eq!(Day02::part_one(&reports), 1);
+    }
+
+    #[test]
+    fn test_min_removals() {
+        let min_removals: Vec<usize> = get_reports()
+            .iter()
+            .map(|report| SafetyPolicy::STRICT.min_removals(report))
+            .collect();
+        assert_eq!(min_removals, vec![0, 2, 2, 1, 1, 0]);
+        assert_eq!(SafetyPolicy::STRICT.min_removals(&[]), 0);
+        assert_eq!(SafetyPolicy::STRICT.min_removals(&[5, 5, 5, 5]), 3);
+    }
+
+    #[test]
+    fn test_custom_policy() {
+        let policy = SafetyPolicy {
+            min_step: 0,
+            max_step: 1,
+            trend: Trend::Increasing,
+            removals: 2,
+        };
+        assert!(policy.is_safe(&[1, 1, 2, 3, 3]));
+        assert!(!policy.is_safe(&[3, 2, 1, 0]));
+        assert_eq!(policy.min_removals(&[1, 9, 2, 9, 9, 3]), 3);
+        assert!(!policy.is_safe(&[1, 9, 2, 9, 9, 3]));
+        assert!(SafetyPolicy {
+            removals: 3,
+            ..policy
+        }
+        .is_safe(&[1, 9, 2, 9, 9, 3]));
+    }
+
+    #[test]
+    fn test_diagnose() {
+        let diagnoses: Vec<Option<Diagnosis>> = get_reports()
+            .iter()
+            .map(|report| SafetyPolicy::STRICT.diagnose(report))
+            .collect();
+        assert_eq!(
+            diagnoses,
+            vec![
+                None,
+                Some(Diagnosis {
+                    offending: 2,
+                    violation: Violation::StepTooLarge,
+                    fix: None,
+                }),
+                Some(Diagnosis {
+                    offending: 3,
+                    violation: Violation::StepTooLarge,
+                    fix: None,
+                }),
+                Some(Diagnosis {
+                    offending: 2,
+                    violation: Violation::DirectionChange,
+                    fix: Some(1),
+                }),
+                Some(Diagnosis {
+                    offending: 3,
+                    violation: Violation::StepTooSmall,
+                    fix: Some(2),
+                }),
+                None,
+            ]
+        );
+        assert_eq!(
+            diagnoses[3].unwrap().annotate(&get_reports()[3]),
+            "1 3 [2] 4 5: direction change, safe without level 2 (3)"
+        );
+        assert_eq!(
+            diagnoses[1].unwrap().annotate(&get_reports()[1]),
+            "1 2 [7] 8 9: step too large, not fixable by removing one level"
+        );
+    }
+
+    /// Reference implementation of the strict rule, independent of [`SafetyPolicy`].
+    fn check_report_safety_brute_force(report: &[i32]) -> bool {
+        (report.iter().is_sorted_by(|a, b| a < b) || report.iter().is_sorted_by(|a, b| a > b))
+            && !(report.windows(2).any(|pair| (pair[1] - pair[0]).abs() > 3))
+    }
+
+    /// Reference implementation trying every single removal.
+    fn check_report_safety_with_problem_dampener_brute_force(report: &[i32]) -> bool {
+        check_report_safety_brute_force(report)
+            || (0..report.len()).any(|idx| {
+                let mut dampened = report.to_vec();
+                dampened.remove(idx);
+                check_report_safety_brute_force(&dampened)
+            })
+    }
+
+    #[test]
+    fn test_problem_dampener_matches_brute_force() {
+        let mut rng = XorShift::new(0x2024_0002);
+        for _ in 0..20_000 {
+            let len = 1 + rng.below(8);
+            // small values produce plenty of equal levels and large jumps
+            let report: Vec<i32> = (0..len).map(|_| rng.below(10) as i32).collect();
+            assert_eq!(
+                check_report_safety(&report),
+                check_report_safety_brute_force(&report),
+                "{:?}",
+                report
+            );
+            let safe = check_report_safety_with_problem_dampener_brute_force(&report);
+            assert_eq!(
+                check_report_safety_with_problem_dampener(&report),
+                safe,
+                "{:?}",
+                report
+            );
+            assert_eq!(
+                SafetyPolicy::STRICT.min_removals(&report) <= 1,
+                safe,
+                "{:?}",
+                report
+            );
         }
     }
 }
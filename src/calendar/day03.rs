@@ -1,86 +1,1198 @@
+use super::{Answer, Answers, Options, Part, Solution};
+use crate::{
+    error::{Location, ParseError},
+    utils::{
+        ansi::{BOLD_GREEN, DIM, GREEN, RESET},
+        for_each_line,
+    },
+};
+use num_bigint::BigInt;
 use rayon::prelude::*;
-use regex::Regex;
 use std::{
-    fs::read_to_string,
-    sync::atomic::{AtomicU64, Ordering},
+    borrow::Cow,
+    collections::VecDeque,
+    fmt,
+    io::{self, BufRead, Write},
+    ops::{Range, RangeInclusive},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+/// Largest number of arguments an instruction can take
+pub const MAX_ARITY: usize = 4;
+/// Largest number of digits of an argument, so that every argument fits an
+/// `u64` and the product of two arguments fits an `i128`
+pub const MAX_DIGITS: usize = 19;
+/// Number of bytes of memory lexed per task by [`lex_chunked`]
+pub const CHUNK_SIZE: usize = 4096;
+
+/// What running an instruction does to the [`Interpreter`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Effect {
+    /// Add the value to the sums
+    Value(i128),
+    Enable,
+    Disable,
+    Toggle,
+}
+
+/// Definition of an instruction written as `name(a,b,...)`.
+#[derive(Debug)]
+pub struct InstructionDef {
+    pub name: &'static str,
+    pub arity: usize,
+    /// Allowed number of decimal digits of each argument
+    pub digits: RangeInclusive<usize>,
+    pub semantics: fn(&[u64]) -> Effect,
+}
+
+/// Instructions are told apart by name
+impl PartialEq for InstructionDef {
+    fn eq(&self, other: &InstructionDef) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for InstructionDef {}
+
+/// Instruction definition the [`Lexer`] cannot read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidInstruction {
+    /// Takes more than [`MAX_ARITY`] arguments
+    Arity { name: &'static str, arity: usize },
+    /// Allows arguments with no number of digits at all, or one outside of `1..=MAX_DIGITS`
+    Digits {
+        name: &'static str,
+        digits: RangeInclusive<usize>,
+    },
+}
+
+impl fmt::Display for InvalidInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidInstruction::Arity { name, arity } => write!(
+                f,
+                "`{}` takes {} arguments, at most {} are supported",
+                name, arity, MAX_ARITY
+            ),
+            InvalidInstruction::Digits { name, digits } => write!(
+                f,
+                "`{}` takes arguments of {} to {} digits, only 1 to {} are supported",
+                name,
+                digits.start(),
+                digits.end(),
+                MAX_DIGITS
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InvalidInstruction {}
+
+impl InstructionDef {
+    /// The same instruction, accepting arguments with a number of digits in
+    /// the given range, or an error if it is empty or does not lie within
+    /// `1..=MAX_DIGITS`.
+    pub fn with_digits(
+        self,
+        digits: RangeInclusive<usize>,
+    ) -> Result<InstructionDef, InvalidInstruction> {
+        let def = InstructionDef { digits, ..self };
+        def.validate()?;
+        Ok(def)
+    }
+
+    /// Check that the lexer can read the instruction.
+    pub fn validate(&self) -> Result<(), InvalidInstruction> {
+        if self.arity > MAX_ARITY {
+            return Err(InvalidInstruction::Arity {
+                name: self.name,
+                arity: self.arity,
+            });
+        }
+        if self.digits.is_empty() || *self.digits.start() < 1 || *self.digits.end() > MAX_DIGITS {
+            return Err(InvalidInstruction::Digits {
+                name: self.name,
+                digits: self.digits.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Length in bytes of the longest memory the instruction can be read from
+    fn max_length(&self) -> usize {
+        self.name.len() + "()".len() + self.arity * self.digits.end() + self.arity.saturating_sub(1)
+    }
+}
+
+/// `mul(a,b)`, with operands of 1 to 3 digits as in the puzzle
+pub const MUL: InstructionDef = InstructionDef {
+    name: "mul",
+    arity: 2,
+    digits: 1..=3,
+    semantics: |args| Effect::Value(args[0] as i128 * args[1] as i128),
+};
+
+pub const DO: InstructionDef = InstructionDef {
+    name: "do",
+    arity: 0,
+    digits: 1..=1,
+    semantics: |_| Effect::Enable,
+};
+
+pub const DONT: InstructionDef = InstructionDef {
+    name: "don't",
+    arity: 0,
+    digits: 1..=1,
+    semantics: |_| Effect::Disable,
 };
 
-fn find_enabled_memory(memory: &String) -> String {
-    let memory = format!("{}{}", "do()", memory.clone());
-    let re_do = Regex::new(r"do\(\)").expect("Failed to create regex");
-    let re_dont = Regex::new(r"don't\(\)").expect("Failed to create regex");
-    let do_offsets = re_do
-        .find_iter(&memory)
-        .map(|m| m.end())
-        .collect::<Vec<usize>>();
-    let dont_offsets = re_dont
-        .find_iter(&memory)
-        .map(|m| m.start())
-        .collect::<Vec<usize>>();
-    let mut enabled_memory = String::new();
-    let mut last_offset = 0;
-    for do_offset in do_offsets {
-        if do_offset > last_offset {
-            if let Some(&dont_offset) = dont_offsets.iter().find(|&&x| x > do_offset) {
-                enabled_memory.push_str(&memory[do_offset..dont_offset]);
-                last_offset = dont_offset;
-            } else {
-                enabled_memory.push_str(&memory[do_offset..]);
+/// Instructions of the puzzle
+pub const STANDARD: &[InstructionDef] = &[MUL, DO, DONT];
+
+/// Number of digits of the operands of `mul`, from 1 up to at most [`MAX_DIGITS`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Digits(usize);
+
+impl Digits {
+    /// Operands of 1 to 3 digits, as in the puzzle
+    pub const PUZZLE: Digits = Digits(3);
+
+    /// Operands of 1 to `max` digits, or an error if `max` is out of range.
+    pub fn up_to(max: usize) -> Result<Digits, InvalidInstruction> {
+        MUL.with_digits(1..=max)?;
+        Ok(Digits(max))
+    }
+
+    /// The instructions of the puzzle, with `mul` taking operands of this width
+    pub fn instructions(self) -> [InstructionDef; 3] {
+        [
+            InstructionDef {
+                digits: 1..=self.0,
+                ..MUL
+            },
+            DO,
+            DONT,
+        ]
+    }
+}
+
+impl Default for Digits {
+    fn default() -> Digits {
+        Digits::PUZZLE
+    }
+}
+
+impl FromStr for Digits {
+    type Err = String;
+
+    fn from_str(digits: &str) -> Result<Digits, String> {
+        let max = digits
+            .parse()
+            .map_err(|_| format!("Invalid number of digits: {}", digits))?;
+        Digits::up_to(max).map_err(|error| error.to_string())
+    }
+}
+
+/// Instruction together with its arguments and the bytes of memory it was read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub instruction: &'a InstructionDef,
+    arguments: [u64; MAX_ARITY],
+    pub span: Range<usize>,
+}
+
+impl Token<'_> {
+    pub fn arguments(&self) -> &[u64] {
+        &self.arguments[..self.instruction.arity]
+    }
+
+    pub fn effect(&self) -> Effect {
+        (self.instruction.semantics)(self.arguments())
+    }
+}
+
+/// Single pass over the memory yielding its well-formed instructions.
+///
+/// Everything that is not exactly one of the defined instructions, with
+/// every argument within its digit limits, is corruption and skipped.
+pub struct Lexer<'m, 'i> {
+    memory: &'m [u8],
+    instructions: &'i [InstructionDef],
+    pos: usize,
+    /// Position from which on no more instructions start, though they may extend past it
+    end: usize,
+}
+
+/// Lex the memory with the given instruction set, e.g. [`STANDARD`], or
+/// return the first instruction the lexer cannot read.
+pub fn lex<'m, 'i>(
+    memory: &'m str,
+    instructions: &'i [InstructionDef],
+) -> Result<Lexer<'m, 'i>, InvalidInstruction> {
+    validate(instructions)?;
+    Ok(lex_range(memory, instructions, 0..memory.len()))
+}
+
+fn validate(instructions: &[InstructionDef]) -> Result<(), InvalidInstruction> {
+    instructions.iter().try_for_each(InstructionDef::validate)
+}
+
+/// Lex the instructions starting within `range` of the memory, with an
+/// instruction set already validated.
+fn lex_range<'m, 'i>(
+    memory: &'m str,
+    instructions: &'i [InstructionDef],
+    range: Range<usize>,
+) -> Lexer<'m, 'i> {
+    Lexer {
+        memory: memory.as_bytes(),
+        instructions,
+        pos: range.start,
+        end: range.end,
+    }
+}
+
+/// Lex the memory in chunks of `chunk_size` bytes in parallel, yielding the
+/// same instructions as [`lex`], or the first instruction the lexer cannot read.
+///
+/// Every chunk is lexed on its own, with instructions starting in the chunk
+/// allowed to extend into the next one. Lexing a chunk can only go wrong if
+/// the instruction straddling its start overlaps with one found in the
+/// chunk, in which case the chunk is lexed again after that instruction.
+pub fn lex_chunked<'i>(
+    memory: &str,
+    instructions: &'i [InstructionDef],
+    chunk_size: usize,
+) -> Result<Vec<Token<'i>>, InvalidInstruction> {
+    validate(instructions)?;
+    let chunks: Vec<Range<usize>> = (0..memory.len())
+        .step_by(chunk_size.max(1))
+        .map(|start| start..(start + chunk_size.max(1)).min(memory.len()))
+        .collect();
+    let lexed: Vec<Vec<Token>> = chunks
+        .par_iter()
+        .map(|chunk| lex_range(memory, instructions, chunk.clone()).collect())
+        .collect();
+    let mut tokens: Vec<Token> = vec![];
+    for (chunk, chunk_tokens) in chunks.into_iter().zip(lexed) {
+        let resume = tokens.last().map_or(0, |token| token.span.end);
+        match chunk_tokens.first() {
+            Some(first) if first.span.start < resume => {
+                tokens.extend(lex_range(memory, instructions, resume..chunk.end))
+            }
+            _ => tokens.extend(chunk_tokens),
+        }
+    }
+    Ok(tokens)
+}
+
+impl<'i> Lexer<'_, 'i> {
+    /// Consume `literal` if the memory continues with it at `pos`.
+    fn literal(&self, pos: usize, literal: &str) -> Option<usize> {
+        self.memory[pos..]
+            .starts_with(literal.as_bytes())
+            .then_some(pos + literal.len())
+    }
+
+    /// Consume a decimal number with an allowed number of digits at `pos`.
+    fn number(&self, pos: usize, digits: &RangeInclusive<usize>) -> Option<(u64, usize)> {
+        let length = self.memory[pos..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count();
+        if !digits.contains(&length) {
+            return None;
+        }
+        let value = self.memory[pos..pos + length]
+            .iter()
+            .fold(0, |value, digit| value * 10 + u64::from(digit - b'0'));
+        Some((value, pos + length))
+    }
+
+    /// Consume the definition's name followed by its parenthesised arguments at `pos`.
+    fn call(&self, pos: usize, def: &'i InstructionDef) -> Option<(Token<'i>, usize)> {
+        let start = pos;
+        let mut pos = self.literal(pos, def.name)?;
+        pos = self.literal(pos, "(")?;
+        let mut arguments = [0; MAX_ARITY];
+        for (idx, argument) in arguments.iter_mut().take(def.arity).enumerate() {
+            if idx > 0 {
+                pos = self.literal(pos, ",")?;
             }
+            (*argument, pos) = self.number(pos, &def.digits)?;
         }
+        pos = self.literal(pos, ")")?;
+        let token = Token {
+            instruction: def,
+            arguments,
+            span: start..pos,
+        };
+        Some((token, pos))
     }
-    enabled_memory
 }
 
-fn mulling_it_over(memory: &String) -> u64 {
-    let product = AtomicU64::new(0);
-    let re = Regex::new(r"mul\(\d+,\d+\)").expect("Failed to create regex");
-    re.find_iter(&memory).par_bridge().for_each(|_match| {
-        let [n1, n2]: [u64; 2] = _match
-            .as_str()
-            .replace("mul(", "")
-            .replace(")", "")
-            .split(",")
-            .collect::<Vec<_>>()
-            .into_iter()
-            .map(|s| s.parse::<u64>().expect("Unable to parse number"))
-            .collect::<Vec<u64>>()
-            .try_into()
-            .expect("Unable to convert vector");
-        product.fetch_add(n1 * n2, Ordering::Relaxed);
-    });
-    product.load(Ordering::Relaxed)
+impl<'i> Iterator for Lexer<'_, 'i> {
+    type Item = Token<'i>;
+
+    fn next(&mut self) -> Option<Token<'i>> {
+        while self.pos < self.end {
+            if let Some((token, end)) = self
+                .instructions
+                .iter()
+                .find_map(|def| self.call(self.pos, def))
+            {
+                self.pos = end;
+                return Some(token);
+            }
+            self.pos += 1;
+        }
+        None
+    }
+}
+
+/// Number the sums of an [`Interpreter`] are kept in
+pub trait Accumulator: Default + Clone + fmt::Display {
+    /// Add `value`, or return `false` and stay unchanged if the sum does not fit.
+    fn add(&mut self, value: i128) -> bool;
+}
+
+/// Checked accumulation, overflowing beyond the range of an `i64`
+impl Accumulator for i64 {
+    fn add(&mut self, value: i128) -> bool {
+        match i64::try_from(value)
+            .ok()
+            .and_then(|value| self.checked_add(value))
+        {
+            Some(sum) => {
+                *self = sum;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Accumulation without overflow
+impl Accumulator for BigInt {
+    fn add(&mut self, value: i128) -> bool {
+        *self += value;
+        true
+    }
 }
 
-pub fn day03() {
-    print!(".---': ~ '(~), ~|        | >@>O< o-_/.()__------|  3: ");
-    let file = read_to_string("src/data/day03.txt").expect("Unable to open file");
+/// Running an instruction took a sum out of the range of its [`Accumulator`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overflow {
+    /// Bytes of memory of the instruction
+    pub span: Range<usize>,
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "arithmetic overflow at bytes {}..{}",
+            self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+/// State of a run over a stream of instructions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interpreter<A = i64> {
+    pub enabled: bool,
+    /// Sum of all values
+    pub total: A,
+    /// Sum of the values produced while enabled
+    pub enabled_total: A,
+}
+
+impl<A: Accumulator> Default for Interpreter<A> {
+    fn default() -> Interpreter<A> {
+        Interpreter {
+            enabled: true,
+            total: A::default(),
+            enabled_total: A::default(),
+        }
+    }
+}
+
+impl<A: Accumulator> Interpreter<A> {
+    /// Run one instruction and return what it added to the enabled total.
+    ///
+    /// On overflow the interpreter is left as it was before the instruction.
+    pub fn step(&mut self, token: &Token) -> Result<i128, Overflow> {
+        match token.effect() {
+            Effect::Value(value) => {
+                let mut total = self.total.clone();
+                let mut enabled_total = self.enabled_total.clone();
+                if !total.add(value) || (self.enabled && !enabled_total.add(value)) {
+                    return Err(Overflow {
+                        span: token.span.clone(),
+                    });
+                }
+                self.total = total;
+                self.enabled_total = enabled_total;
+                if self.enabled {
+                    return Ok(value);
+                }
+            }
+            Effect::Enable => self.enabled = true,
+            Effect::Disable => self.enabled = false,
+            Effect::Toggle => self.enabled = !self.enabled,
+        }
+        Ok(0)
+    }
+}
+
+/// Run the instructions, starting enabled, with sums checked to fit an `i64`.
+pub fn evaluate<'a, 'b: 'a>(
+    tokens: impl IntoIterator<Item = &'a Token<'b>>,
+) -> Result<Interpreter, Overflow> {
+    run(tokens)
+}
+
+/// Run the instructions, starting enabled, with sums of arbitrary size.
+pub fn evaluate_big<'a, 'b: 'a>(
+    tokens: impl IntoIterator<Item = &'a Token<'b>>,
+) -> Interpreter<BigInt> {
+    run(tokens).expect("Big integers do not overflow")
+}
+
+fn run<'a, 'b: 'a, A: Accumulator>(
+    tokens: impl IntoIterator<Item = &'a Token<'b>>,
+) -> Result<Interpreter<A>, Overflow> {
+    let mut interpreter = Interpreter::default();
+    for token in tokens {
+        interpreter.step(token)?;
+    }
+    Ok(interpreter)
+}
+
+/// Location in the input of the byte at `offset` of its [`memory`]
+fn location(input: &str, mut offset: usize, newlines: Newlines) -> Location {
+    if newlines == Newlines::Memory {
+        let line_start = input[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+        let line_idx = input[..line_start].matches('\n').count();
+        return Location::new(line_idx + 1, input[line_start..offset].chars().count() + 1);
+    }
+    for (line_idx, line) in input.lines().enumerate() {
+        if offset < line.len() {
+            return Location::new(line_idx + 1, line[..offset].chars().count() + 1);
+        }
+        offset -= line.len();
+    }
+    Location::new(input.lines().count().max(1), 1)
+}
 
-    let _conditional_product = AtomicU64::new(0);
-    let memory = file.lines().collect::<Vec<_>>().concat();
-    let product = mulling_it_over(&memory);
-    print!("Full result: {:?}, ", product);
-    let enabled_memory = find_enabled_memory(&memory);
-    let product = mulling_it_over(&enabled_memory);
-    println!("only enabled memory result: {:?}", product);
+/// How the line breaks of the input are treated
+///
+/// Up to the introduction of this setting lines were always joined; the
+/// default is now to keep them as part of the memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Newlines {
+    /// Line breaks are part of the memory, breaking up instructions like any other corruption
+    #[default]
+    Memory,
+    /// Lines are joined without separator, so that instructions may continue on the next line
+    Join,
+}
+
+impl FromStr for Newlines {
+    type Err = String;
+
+    fn from_str(newlines: &str) -> Result<Newlines, String> {
+        match newlines {
+            "memory" => Ok(Newlines::Memory),
+            "join" => Ok(Newlines::Join),
+            _ => Err(format!("Invalid newline mode: {}", newlines)),
+        }
+    }
+}
+
+/// The memory of the puzzle input
+pub fn memory(input: &str, newlines: Newlines) -> Cow<'_, str> {
+    match newlines {
+        Newlines::Memory => Cow::Borrowed(input),
+        Newlines::Join => Cow::Owned(input.lines().collect::<Vec<_>>().concat()),
+    }
+}
+
+/// Evaluation of a memory fed line by line, for inputs too large to be held at once.
+///
+/// Only the end of the memory that may still be the start of an instruction
+/// is kept between lines. With [`Newlines::Memory`] no instruction spans
+/// lines, so every line is lexed on its own and nothing is kept at all.
+pub struct StreamEvaluator<'i, A = i64> {
+    instructions: &'i [InstructionDef],
+    newlines: Newlines,
+    /// Length in bytes of the longest instruction
+    max_length: usize,
+    /// Joined memory not lexed yet
+    pending: String,
+    /// Offset in the memory of the start of `pending`
+    consumed: usize,
+    /// Lines with a part in `pending`, in order
+    lines: VecDeque<PendingLine>,
+    interpreter: Interpreter<A>,
+}
+
+/// Line of the input whose end is still pending
+struct PendingLine {
+    /// Offset in the memory of the start of the line
+    start: usize,
+    line_idx: usize,
+    /// Number of characters of the line already dropped from `pending`
+    dropped: usize,
+}
+
+impl<'i, A: Accumulator> StreamEvaluator<'i, A> {
+    /// Start evaluating, or return the first instruction the lexer cannot read.
+    pub fn new(
+        instructions: &'i [InstructionDef],
+        newlines: Newlines,
+    ) -> Result<StreamEvaluator<'i, A>, InvalidInstruction> {
+        validate(instructions)?;
+        Ok(StreamEvaluator {
+            instructions,
+            newlines,
+            max_length: instructions
+                .iter()
+                .map(InstructionDef::max_length)
+                .max()
+                .unwrap_or(0),
+            pending: String::new(),
+            consumed: 0,
+            lines: VecDeque::new(),
+            interpreter: Interpreter::default(),
+        })
+    }
+
+    /// Run the instructions of the next line, given without its line break.
+    pub fn push_line(&mut self, line_idx: usize, line: &str) -> Result<(), ParseError> {
+        if self.newlines == Newlines::Memory {
+            for token in lex_range(line, self.instructions, 0..line.len()) {
+                self.interpreter
+                    .step(&token)
+                    .map_err(|overflow| ParseError::Overflow {
+                        token: line[overflow.span.clone()].to_string(),
+                        at: Location::of_token(line_idx, line, &line[overflow.span]),
+                    })?;
+            }
+            return Ok(());
+        }
+        self.lines.push_back(PendingLine {
+            start: self.consumed + self.pending.len(),
+            line_idx,
+            dropped: 0,
+        });
+        self.pending.push_str(line);
+        // instructions starting any later may continue on the next line
+        let end = (self.pending.len() + 1).saturating_sub(self.max_length);
+        self.run_pending(end)
+    }
+
+    /// Run the instructions at the end of the memory and return the final state.
+    pub fn finish(mut self) -> Result<Interpreter<A>, ParseError> {
+        self.run_pending(self.pending.len())?;
+        Ok(self.interpreter)
+    }
+
+    /// Run the pending instructions starting before `end` and drop the memory lexed.
+    fn run_pending(&mut self, end: usize) -> Result<(), ParseError> {
+        let mut lexer = lex_range(&self.pending, self.instructions, 0..end);
+        for token in lexer.by_ref() {
+            if let Err(overflow) = self.interpreter.step(&token) {
+                return Err(ParseError::Overflow {
+                    token: self.pending[overflow.span.clone()].to_string(),
+                    at: self.location(overflow.span.start),
+                });
+            }
+        }
+        let mut lexed = lexer.pos;
+        while !self.pending.is_char_boundary(lexed) {
+            lexed -= 1;
+        }
+        let dropped_to = self.consumed + lexed;
+        while self.lines.len() > 1 && self.lines[1].start <= dropped_to {
+            self.lines.pop_front();
+        }
+        if let Some(line) = self.lines.front_mut() {
+            let from = line.start.max(self.consumed) - self.consumed;
+            line.dropped += self.pending[from..lexed].chars().count();
+        }
+        self.pending.drain(..lexed);
+        self.consumed = dropped_to;
+        Ok(())
+    }
+
+    /// Location in the input of the byte at `offset` of `pending`
+    fn location(&self, offset: usize) -> Location {
+        let line = self
+            .lines
+            .iter()
+            .rev()
+            .find(|line| line.start <= self.consumed + offset)
+            .expect("Pending memory lies on pending lines");
+        let from = line.start.max(self.consumed) - self.consumed;
+        Location::new(
+            line.line_idx + 1,
+            line.dropped + self.pending[from..offset].chars().count() + 1,
+        )
+    }
+}
+
+/// Record of running one instruction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    pub span: Range<usize>,
+    /// Whether the interpreter was enabled when reaching the instruction
+    pub enabled: bool,
+    /// What the instruction added to the enabled total
+    pub contribution: i128,
+    /// Enabled total after the instruction
+    pub running_sum: i64,
+}
+
+/// Run the instructions, starting enabled, recording every step up to the
+/// first overflow, if any.
+pub fn trace(tokens: &[Token]) -> (Vec<TraceStep>, Option<Overflow>) {
+    let mut interpreter: Interpreter = Interpreter::default();
+    let mut steps = vec![];
+    for token in tokens {
+        let enabled = interpreter.enabled;
+        match interpreter.step(token) {
+            Ok(contribution) => steps.push(TraceStep {
+                span: token.span.clone(),
+                enabled,
+                contribution,
+                running_sum: interpreter.enabled_total,
+            }),
+            Err(overflow) => return (steps, Some(overflow)),
+        }
+    }
+    (steps, None)
+}
+
+/// Write the trace as a table, one instruction per line.
+pub fn write_trace(out: &mut impl Write, memory: &str, steps: &[TraceStep]) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:>8}  {:<16} {:<7} {:>12} {:>12}",
+        "offset", "instruction", "enabled", "contribution", "running sum"
+    )?;
+    for step in steps {
+        writeln!(
+            out,
+            "{:>8}  {:<16} {:<7} {:>12} {:>12}",
+            step.span.start,
+            &memory[step.span.clone()],
+            if step.enabled { "yes" } else { "no" },
+            step.contribution,
+            step.running_sum
+        )?;
+    }
+    Ok(())
+}
+
+/// The memory with ANSI colours: enabled regions green, with the
+/// instructions counted in bold, and disabled regions dimmed.
+pub fn highlight(memory: &str, tokens: &[Token]) -> String {
+    let mut highlighted = String::new();
+    let mut paint = |text: &str, colour: &str| {
+        if !text.is_empty() {
+            highlighted.extend([colour, text, RESET]);
+        }
+    };
+    let region = |enabled: bool| if enabled { GREEN } else { DIM };
+    let mut interpreter: Interpreter<BigInt> = Interpreter::default();
+    let mut cursor = 0;
+    for token in tokens {
+        paint(
+            &memory[cursor..token.span.start],
+            region(interpreter.enabled),
+        );
+        let counted = matches!(token.effect(), Effect::Value(_)) && interpreter.enabled;
+        interpreter
+            .step(token)
+            .expect("Big integers do not overflow");
+        let colour = if counted {
+            BOLD_GREEN
+        } else {
+            region(interpreter.enabled)
+        };
+        paint(&memory[token.span.clone()], colour);
+        cursor = token.span.end;
+    }
+    paint(&memory[cursor..], region(interpreter.enabled));
+    highlighted
+}
+
+/// Lex the memory with instructions that are known to be readable.
+fn lex_memory<'i>(memory: &str, instructions: &'i [InstructionDef]) -> Vec<Token<'i>> {
+    lex_chunked(memory, instructions, CHUNK_SIZE).expect("Instructions are checked beforehand")
+}
+
+/// Error for an overflow while running `memory`, read from `input`
+fn overflow_error(input: &str, memory: &str, overflow: Overflow, newlines: Newlines) -> ParseError {
+    ParseError::Overflow {
+        token: memory[overflow.span.clone()].to_string(),
+        at: location(input, overflow.span.start, newlines),
+    }
+}
+
+/// Both sums of a finished run, rendered for printing
+fn sums<A: Accumulator>(interpreter: Interpreter<A>) -> [String; 2] {
+    [
+        interpreter.total.to_string(),
+        interpreter.enabled_total.to_string(),
+    ]
+}
+
+fn answers(parts: &[Part], [total, enabled_total]: [String; 2], duration: Duration) -> Answers {
+    parts
+        .iter()
+        .map(|&part| Answer {
+            part,
+            value: match part {
+                Part::One => total.clone(),
+                Part::Two => enabled_total.clone(),
+            },
+            duration,
+        })
+        .collect()
+}
+
+/// Answer the requested parts with the newline mode, operand width and kind
+/// of sums set in the options.
+///
+/// Both parts are summed together, so each answer carries the duration of the whole run.
+pub fn solve_with(input: &str, parts: &[Part], options: &Options) -> Result<Answers, ParseError> {
+    let start = Instant::now();
+    let instructions = options.digits.instructions();
+    let memory = memory(input, options.newlines);
+    let tokens = lex_memory(&memory, &instructions);
+    let sums = if options.big_int {
+        sums(evaluate_big(&tokens))
+    } else {
+        sums(
+            evaluate(&tokens)
+                .map_err(|overflow| overflow_error(input, &memory, overflow, options.newlines))?,
+        )
+    };
+    Ok(answers(parts, sums, start.elapsed()))
+}
+
+/// Run the memory read line by line with sums kept in `A`.
+fn stream_sums<A: Accumulator>(
+    reader: &mut dyn BufRead,
+    instructions: &[InstructionDef],
+    newlines: Newlines,
+) -> Result<[String; 2], ParseError> {
+    let mut evaluator: StreamEvaluator<A> =
+        StreamEvaluator::new(instructions, newlines).expect("Instructions are checked beforehand");
+    for_each_line(reader, |line_idx, line| evaluator.push_line(line_idx, line))?;
+    Ok(sums(evaluator.finish()?))
+}
+
+/// Answer the requested parts in a single pass over a reader, holding on to
+/// one line of the memory at a time.
+///
+/// Both parts are summed together, so each answer carries the duration of the whole pass.
+pub fn solve_stream(
+    reader: &mut dyn BufRead,
+    parts: &[Part],
+    options: &Options,
+) -> Result<Answers, ParseError> {
+    let start = Instant::now();
+    let instructions = options.digits.instructions();
+    let sums = if options.big_int {
+        stream_sums::<BigInt>(reader, &instructions, options.newlines)?
+    } else {
+        stream_sums::<i64>(reader, &instructions, options.newlines)?
+    };
+    Ok(answers(parts, sums, start.elapsed()))
+}
+
+pub struct Day03;
+
+impl Solution for Day03 {
+    const DAY: u8 = 3;
+    const ART: &'static str = r".---': ~ '(~), ~|        | >@>O< o-_/.()__------| ";
+    const INPUT: &'static str = "src/data/day03.txt";
+    const LABELS: [&'static str; 2] = ["Full result", "only enabled memory result"];
+
+    type Input = Vec<Token<'static>>;
+    type PartOne = i64;
+    type PartTwo = i64;
+
+    /// Lex the memory and make sure that evaluating it does not overflow.
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        let newlines = Newlines::default();
+        let memory = memory(input, newlines);
+        let tokens = lex_memory(&memory, STANDARD);
+        evaluate(&tokens).map_err(|overflow| overflow_error(input, &memory, overflow, newlines))?;
+        Ok(tokens)
+    }
+
+    fn part_one(tokens: &Self::Input) -> i64 {
+        evaluate(tokens).expect("Checked while parsing").total
+    }
+
+    fn part_two(tokens: &Self::Input) -> i64 {
+        evaluate(tokens)
+            .expect("Checked while parsing")
+            .enabled_total
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::rng::XorShift;
+
+    fn summary(tokens: &[Token]) -> Vec<(&'static str, Vec<u64>, Range<usize>)> {
+        tokens
+            .iter()
+            .map(|token| {
+                (
+                    token.instruction.name,
+                    token.arguments().to_vec(),
+                    token.span.clone(),
+                )
+            })
+            .collect()
+    }
 
     #[test]
     fn test_mulling_it_over() {
-        let memory =
-            "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))".to_string();
-        assert_eq!(mulling_it_over(&memory), 161);
+        let memory = "xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))";
+        let tokens: Vec<Token> = lex(memory, STANDARD).unwrap().collect();
+        assert_eq!(evaluate(&tokens).unwrap().total, 161);
     }
 
     #[test]
     fn test_mulling_it_over_enabled() {
-        let memory =
-            "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))".to_string();
-        let enabled_memory = find_enabled_memory(&memory);
-        assert_eq!(mulling_it_over(&enabled_memory), 48);
+        let memory = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        let tokens: Vec<Token> = lex(memory, STANDARD).unwrap().collect();
+        let interpreter = evaluate(&tokens).unwrap();
+        assert_eq!((interpreter.total, interpreter.enabled_total), (161, 48));
+    }
+
+    #[test]
+    fn test_lex() {
+        let memory = "mul(1,22)don't()mul( 3,4)mmul(5,6do()mul(1234,1)";
+        let tokens: Vec<Token> = lex(memory, STANDARD).unwrap().collect();
+        assert_eq!(
+            summary(&tokens),
+            vec![
+                ("mul", vec![1, 22], 0..9),
+                ("don't", vec![], 9..16),
+                ("do", vec![], 33..37),
+            ]
+        );
+        assert_eq!(&memory[tokens[1].span.clone()], "don't()");
+    }
+
+    #[test]
+    fn test_trace() {
+        let memory = "mul(2,4)don't()mul(5,5)do()mul(8,5)";
+        let tokens: Vec<Token> = lex(memory, STANDARD).unwrap().collect();
+        let (steps, overflow) = trace(&tokens);
+        assert_eq!(overflow, None);
+        let summary: Vec<(usize, bool, i128, i64)> = steps
+            .iter()
+            .map(|step| {
+                (
+                    step.span.start,
+                    step.enabled,
+                    step.contribution,
+                    step.running_sum,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (0, true, 8, 8),
+                (8, true, 0, 8),
+                (15, false, 0, 8),
+                (23, false, 0, 8),
+                (27, true, 40, 48),
+            ]
+        );
+        let mut out = vec![];
+        write_trace(&mut out, memory, &steps).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(
+            out.lines().nth(2).unwrap(),
+            "       8  don't()          yes                0            8"
+        );
+    }
+
+    #[test]
+    fn test_highlight() {
+        let memory = "xmul(2,4)don't()mul(5,5)";
+        let tokens: Vec<Token> = lex(memory, STANDARD).unwrap().collect();
+        assert_eq!(
+            highlight(memory, &tokens),
+            format!(
+                "{GREEN}x{RESET}{BOLD_GREEN}mul(2,4){RESET}{DIM}don't(){RESET}{DIM}mul(5,5){RESET}"
+            )
+        );
+    }
+
+    #[test]
+    fn test_custom_instructions() {
+        const INSTRUCTIONS: &[InstructionDef] = &[
+            MUL,
+            InstructionDef {
+                name: "add",
+                arity: 2,
+                digits: 1..=3,
+                semantics: |args| Effect::Value(args[0] as i128 + args[1] as i128),
+            },
+            InstructionDef {
+                name: "neg",
+                arity: 1,
+                digits: 1..=3,
+                semantics: |args| Effect::Value(-(args[0] as i128)),
+            },
+            InstructionDef {
+                name: "if",
+                arity: 1,
+                digits: 1..=1,
+                semantics: |args| match args[0] {
+                    0 => Effect::Disable,
+                    _ => Effect::Enable,
+                },
+            },
+            InstructionDef {
+                name: "flip",
+                arity: 0,
+                digits: 1..=1,
+                semantics: |_| Effect::Toggle,
+            },
+        ];
+        let memory = "add(1,2)xneg(10)if(0)mul(2,3)if(7)add(1000,1)flip()neg(4)flip()do()mul(1,1)";
+        let tokens: Vec<Token> = lex(memory, INSTRUCTIONS).unwrap().collect();
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|token| token.instruction.name)
+                .collect::<Vec<_>>(),
+            ["add", "neg", "if", "mul", "if", "flip", "neg", "flip", "mul"]
+        );
+        let interpreter = evaluate(&tokens).unwrap();
+        assert_eq!((interpreter.total, interpreter.enabled_total), (-4, -6));
+    }
+
+    #[test]
+    fn test_operand_width() {
+        let memory = "mul(1234,5)mul(12,345)";
+        assert_eq!(lex(memory, STANDARD).unwrap().count(), 1);
+        let wide = [MUL.with_digits(1..=4).unwrap()];
+        let tokens: Vec<Token> = lex(memory, &wide).unwrap().collect();
+        assert_eq!(evaluate(&tokens).unwrap().total, 1234 * 5 + 12 * 345);
+
+        assert_eq!(
+            MUL.with_digits(0..=3),
+            Err(InvalidInstruction::Digits {
+                name: "mul",
+                digits: 0..=3
+            })
+        );
+        assert!(MUL.with_digits(1..=MAX_DIGITS + 1).is_err());
+        let five = InstructionDef {
+            name: "five",
+            arity: MAX_ARITY + 1,
+            digits: 1..=3,
+            semantics: |_| Effect::Toggle,
+        };
+        let instructions = [MUL, five];
+        assert!(matches!(
+            lex(memory, &instructions),
+            Err(InvalidInstruction::Arity { arity: 5, .. })
+        ));
+        assert!(lex_chunked(memory, &instructions, CHUNK_SIZE).is_err());
+        assert!(StreamEvaluator::<i64>::new(&instructions, Newlines::Memory).is_err());
+
+        assert_eq!("4".parse(), Ok(Digits::up_to(4).unwrap()));
+        assert!("20".parse::<Digits>().is_err());
+        assert!("0".parse::<Digits>().is_err());
+        let options = Options {
+            digits: "4".parse().unwrap(),
+            ..Options::default()
+        };
+        let answers = solve_with(memory, &[Part::One], &options).unwrap();
+        assert_eq!(answers[0].value, (1234 * 5 + 12 * 345).to_string());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let wide = [MUL.with_digits(1..=MAX_DIGITS).unwrap(), DONT];
+        let memory = "mul(3,4)don't()mul(9999999999,9999999999)mul(1,1)";
+        let tokens: Vec<Token> = lex(memory, &wide).unwrap().collect();
+        assert_eq!(evaluate(&tokens), Err(Overflow { span: 15..41 }));
+        let (steps, overflow) = trace(&tokens);
+        assert_eq!(
+            (steps.len(), overflow),
+            (2, Some(Overflow { span: 15..41 }))
+        );
+        let interpreter = evaluate_big(&tokens);
+        assert_eq!(interpreter.total.to_string(), "99999999980000000014");
+        assert_eq!(interpreter.enabled_total, BigInt::from(12));
+
+        let max = "mul(9999999999999999999,9999999999999999999)";
+        let tokens: Vec<Token> = lex(max, &wide).unwrap().collect();
+        let product = 9_999_999_999_999_999_999i128 * 9_999_999_999_999_999_999;
+        let mut total = BigInt::from(product);
+        assert_eq!(evaluate_big(&tokens).total, total);
+        total += product;
+        assert_eq!(evaluate_big(tokens.iter().chain(&tokens)).total, total);
+    }
+
+    #[test]
+    fn test_big_int_option() {
+        let input = "mul(9999999999,9999999999)\nmul(2,3)";
+        let mut options = Options {
+            digits: Digits::up_to(10).unwrap(),
+            ..Options::default()
+        };
+        assert_eq!(
+            solve_with(input, &Part::BOTH, &options),
+            Err(ParseError::Overflow {
+                token: "mul(9999999999,9999999999)".to_string(),
+                at: Location::new(1, 1),
+            })
+        );
+        assert!(solve_stream(&mut input.as_bytes(), &Part::BOTH, &options).is_err());
+        options.big_int = true;
+        for answers in [
+            solve_with(input, &Part::BOTH, &options).unwrap(),
+            solve_stream(&mut input.as_bytes(), &Part::BOTH, &options).unwrap(),
+        ] {
+            let values: Vec<&str> = answers.iter().map(|answer| answer.value.as_str()).collect();
+            assert_eq!(values, ["99999999980000000007"; 2]);
+        }
+    }
+
+    #[test]
+    fn test_location() {
+        let input = "ab\ncdef\n";
+        assert_eq!(location(input, 3, Newlines::Join), Location::new(2, 2));
+        assert_eq!(location(input, 0, Newlines::Join), Location::new(1, 1));
+        assert_eq!(location(input, 4, Newlines::Memory), Location::new(2, 2));
+        assert_eq!(location(input, 1, Newlines::Memory), Location::new(1, 2));
+    }
+
+    #[test]
+    fn test_newlines() {
+        let input = "mul(1\n,2)mul(3,4)\n";
+        let count = |newlines| lex(&memory(input, newlines), STANDARD).unwrap().count();
+        assert_eq!(count(Newlines::Memory), 1);
+        assert_eq!(count(Newlines::Join), 2);
+    }
+
+    #[test]
+    fn test_stream_evaluator_matches_evaluate() {
+        let fragments = [
+            "mul(", "1", "23", ",", ")", "do()", "don't()", "\n", "é", "x",
+        ];
+        let mut rng = XorShift::new(0x2024_0303);
+        for _ in 0..500 {
+            let length = rng.below(40);
+            let input: String = (0..length)
+                .map(|_| fragments[rng.below(fragments.len())])
+                .collect();
+            for newlines in [Newlines::Memory, Newlines::Join] {
+                let tokens: Vec<Token> =
+                    lex(&memory(&input, newlines), STANDARD).unwrap().collect();
+                let mut evaluator = StreamEvaluator::new(STANDARD, newlines).unwrap();
+                for_each_line(input.as_bytes(), |line_idx, line| {
+                    evaluator.push_line(line_idx, line)
+                })
+                .unwrap();
+                assert_eq!(
+                    evaluator.finish(),
+                    Ok(evaluate(&tokens).unwrap()),
+                    "{:?} with {:?}",
+                    input,
+                    newlines
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_overflow_location() {
+        let wide = [MUL.with_digits(1..=MAX_DIGITS).unwrap()];
+        let input = "mul(2,2)\néxmu\nl(9999999999,9999999999)";
+        let mut evaluator: StreamEvaluator = StreamEvaluator::new(&wide, Newlines::Join).unwrap();
+        let error = for_each_line(input.as_bytes(), |line_idx, line| {
+            evaluator.push_line(line_idx, line)
+        })
+        .and_then(|_| evaluator.finish().map(|_| ()));
+        assert_eq!(
+            error,
+            Err(ParseError::Overflow {
+                token: "mul(9999999999,9999999999)".to_string(),
+                at: Location::new(2, 3),
+            })
+        );
+        let answers = solve_stream(
+            &mut input.as_bytes(),
+            &[Part::Two],
+            &Options {
+                newlines: Newlines::Join,
+                ..Options::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(answers[0].value, "4");
+    }
+
+    #[test]
+    fn test_lex_chunked_matches_lex() {
+        // `b()` can start inside `ab()`, so chunk starts can fall into overlapping instructions
+        let instructions = [
+            MUL,
+            DO,
+            InstructionDef {
+                name: "ab",
+                arity: 0,
+                digits: 1..=1,
+                semantics: |_| Effect::Toggle,
+            },
+            InstructionDef {
+                name: "b",
+                arity: 0,
+                digits: 1..=1,
+                semantics: |_| Effect::Disable,
+            },
+        ];
+        let fragments = [
+            "mul(", "1", "23", ",", ")", "do()", "ab()", "b()", "a", "(", "x",
+        ];
+        let mut rng = XorShift::new(0x2024_0003);
+        for _ in 0..500 {
+            let length = rng.below(32);
+            let memory: String = (0..length)
+                .map(|_| fragments[rng.below(fragments.len())])
+                .collect();
+            let expected: Vec<Token> = lex(&memory, &instructions).unwrap().collect();
+            for chunk_size in [1, 2, 3, 5, 8, 64] {
+                assert_eq!(
+                    lex_chunked(&memory, &instructions, chunk_size).unwrap(),
+                    expected,
+                    "{} in chunks of {}",
+                    memory,
+                    chunk_size
+                );
+            }
+        }
     }
 }
@@ -1,156 +1,407 @@
-use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
-use std::{
-    fs::read_to_string,
-    sync::atomic::{AtomicU64, Ordering},
+use super::Solution;
+use crate::{
+    error::ParseError,
+    utils::{
+        ansi::{DIM, RESET},
+        Dir8, Grid, Point,
+    },
 };
+use aho_corasick::AhoCorasick;
+use rayon::prelude::*;
+use std::collections::HashSet;
 
-#[derive(Debug)]
-enum Move {
-    Up,
-    UpRight,
-    Right,
-    DownRight,
-    Down,
-    DownLeft,
-    Left,
-    UpLeft,
+/// What happens to a word reaching the edge of the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    /// The word has to fit inside the grid
+    Clip,
+    /// The word continues from the opposite edge, the grid being a torus
+    Wrap,
 }
 
-impl Move {
-    fn get(&self) -> (i32, i32) {
+/// Directions in which words are read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Directions {
+    /// Horizontally and vertically, both ways
+    Orthogonal,
+    /// Along both diagonals, both ways
+    Diagonal,
+    /// All eight directions
+    All,
+    /// Each line only in its natural reading direction: rows left to right,
+    /// columns and diagonals top to bottom
+    Forward,
+}
+
+impl Directions {
+    pub fn directions(self) -> &'static [Dir8] {
         match self {
-            Move::Up => (0, -1),
-            Move::UpRight => (1, -1),
-            Move::Right => (1, 0),
-            Move::DownRight => (1, 1),
-            Move::Down => (0, 1),
-            Move::DownLeft => (-1, 1),
-            Move::Left => (-1, 0),
-            Move::UpLeft => (-1, -1),
+            Directions::Orthogonal => &[Dir8::Up, Dir8::Right, Dir8::Down, Dir8::Left],
+            Directions::Diagonal => &[Dir8::UpRight, Dir8::DownRight, Dir8::DownLeft, Dir8::UpLeft],
+            Directions::All => &Dir8::ALL,
+            Directions::Forward => &[Dir8::Right, Dir8::DownRight, Dir8::Down, Dir8::DownLeft],
         }
     }
+}
 
-    fn iter() -> impl Iterator<Item = Move> {
-        [
-            Move::Up,
-            Move::UpRight,
-            Move::Right,
-            Move::DownRight,
-            Move::Down,
-            Move::DownLeft,
-            Move::Left,
-            Move::UpLeft,
-        ]
-        .into_iter()
+/// How letters of the word are compared with the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    Sensitive,
+    Insensitive,
+}
+
+impl Case {
+    fn matches(self, a: char, b: char) -> bool {
+        match self {
+            Case::Sensitive => a == b,
+            Case::Insensitive => a == b || a.to_lowercase().eq(b.to_lowercase()),
+        }
     }
 }
 
-struct Position {
-    col: i32,
-    row: i32,
+/// Rules of a word search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchConfig {
+    pub boundary: Boundary,
+    pub directions: Directions,
+    pub case: Case,
 }
 
-fn count_word_in_grid(grid: &Vec<Vec<char>>, search_word: &str) -> u64 {
-    let word_count = AtomicU64::new(0);
-    let n_cols = grid.len();
-    let n_rows = grid.get(0).expect("No rows").len();
-    grid.par_iter().enumerate().for_each(|(row_idx, col)| {
-        if col.len() != n_rows {
-            panic!("Not all rows have equal length")
-        }
-        col.par_iter().enumerate().for_each(|(col_idx, &c)| {
-            if search_word.starts_with(c) {
-                if search_word.len() == 1 {
-                    word_count.fetch_add(1, Ordering::Relaxed);
-                } else {
-                    // explore moves in all directions for viable candidate directions
-                    for _move in Move::iter() {
-                        let (d_col, d_row) = _move.get();
-                        let mut pos = Position {
-                            col: col_idx as i32 + d_col,
-                            row: row_idx as i32 + d_row,
-                        };
-                        let mut offset: usize = 1;
-                        let mut next_search_word_char = search_word.chars().nth(offset).unwrap();
-                        while !(pos.col < 0
-                            || pos.row < 0
-                            || pos.col >= n_cols as i32
-                            || pos.row >= n_rows as i32)
-                            && grid[pos.row as usize][pos.col as usize] == next_search_word_char
-                            && offset < search_word.len()
-                        {
-                            offset += 1;
-                            if let Some(search_word_char) = search_word.chars().nth(offset) {
-                                next_search_word_char = search_word_char;
-                            } else {
-                                word_count.fetch_add(1, Ordering::Relaxed);
-                                break;
-                            };
-                            pos.col += d_col;
-                            pos.row += d_row;
+impl SearchConfig {
+    /// The puzzle's rules: words inside the grid, in any of the eight
+    /// directions, with exactly matching letters
+    pub const STANDARD: SearchConfig = SearchConfig {
+        boundary: Boundary::Clip,
+        directions: Directions::All,
+        case: Case::Sensitive,
+    };
+}
+
+impl Default for SearchConfig {
+    fn default() -> SearchConfig {
+        SearchConfig::STANDARD
+    }
+}
+
+/// Number of occurrences of the word under the rules of the search, as found by [`find_word_in_grid`].
+pub fn count_word_in_grid(grid: &Grid<char>, search_word: &str, config: &SearchConfig) -> u64 {
+    if *config == SearchConfig::STANDARD {
+        count_words_in_grid(grid, &[search_word])[0]
+    } else {
+        find_word_in_grid(grid, search_word, config).len() as u64
+    }
+}
+
+/// Number of occurrences of each word in any of the eight directions, in the order of `words`.
+///
+/// Every row, column and diagonal is read once and searched for all words
+/// and their reversals at the same time by an Aho–Corasick automaton, so
+/// that reading a line backwards is covered by the reversed words. Words of
+/// a single character are counted once per cell, as their direction does
+/// not matter.
+pub fn count_words_in_grid(grid: &Grid<char>, words: &[&str]) -> Vec<u64> {
+    let mut counts = vec![0; words.len()];
+    // each distinct pattern with the words it counts for, a palindrome counting twice
+    let mut patterns: Vec<String> = vec![];
+    let mut pattern_words: Vec<Vec<usize>> = vec![];
+    for (idx, word) in words.iter().enumerate() {
+        let mut chars = word.chars();
+        match (chars.next(), chars.next()) {
+            (None, _) => {}
+            (Some(c), None) => {
+                counts[idx] = grid.cells().iter().filter(|&&cell| cell == c).count() as u64
+            }
+            _ => {
+                for pattern in [word.to_string(), word.chars().rev().collect()] {
+                    match patterns.iter().position(|known| *known == pattern) {
+                        Some(pattern_idx) => pattern_words[pattern_idx].push(idx),
+                        None => {
+                            patterns.push(pattern);
+                            pattern_words.push(vec![idx]);
                         }
                     }
                 }
             }
+        }
+    }
+    if patterns.is_empty() {
+        return counts;
+    }
+    let automaton = AhoCorasick::new(&patterns).expect("Unable to build automaton");
+    let lines: Vec<String> = grid
+        .rows()
+        .map(|row| row.iter().collect())
+        .chain(grid.columns().map(read_line))
+        .chain(grid.diagonals().map(read_line))
+        .chain(grid.anti_diagonals().map(read_line))
+        .collect();
+    let line_counts = lines
+        .par_iter()
+        .map(|line| {
+            let mut line_counts = vec![0; words.len()];
+            for found in automaton.find_overlapping_iter(line) {
+                for &idx in &pattern_words[found.pattern()] {
+                    line_counts[idx] += 1;
+                }
+            }
+            line_counts
         })
+        .reduce(
+            || vec![0; words.len()],
+            |mut a, b| {
+                a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+                a
+            },
+        );
+    counts
+        .iter_mut()
+        .zip(line_counts)
+        .for_each(|(a, b)| *a += b);
+    counts
+}
+
+fn read_line<'a>(line: impl Iterator<Item = (Point, &'a char)>) -> String {
+    line.map(|(_, &c)| c).collect()
+}
+
+/// Occurrence of a word, read from `start` towards `direction` up to `end`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WordMatch {
+    pub start: Point,
+    pub direction: Dir8,
+    pub end: Point,
+    /// Number of letters
+    pub length: usize,
+}
+
+impl WordMatch {
+    /// Positions of the letters, from `start` onwards.
+    ///
+    /// They are not wrapped back into the grid, so on a torus they may lie
+    /// outside of it, see [`Grid::wrap`].
+    pub fn cells(&self) -> impl Iterator<Item = Point> {
+        let (start, step) = (self.start, self.direction.delta());
+        (0..self.length as isize).map(move |idx| start + step * idx)
+    }
+}
+
+/// Every occurrence of the word under the rules of the search, ordered by start cell.
+///
+/// Occurrences reading the same cells in the same order are reported once,
+/// with the first of the allowed directions, orthogonal ones first. So a palindrome is found once
+/// per reading direction and a word of a single character once per cell,
+/// while on a torus a word running back over its own cells, e.g. `ABAB`
+/// along a row `AB`, is not also found in the opposite direction.
+pub fn find_word_in_grid(grid: &Grid<char>, word: &str, config: &SearchConfig) -> Vec<WordMatch> {
+    let letters: Vec<char> = word.chars().collect();
+    if letters.is_empty() {
+        return vec![];
+    }
+    let cell = |point: Point| match config.boundary {
+        Boundary::Clip => grid.get(point),
+        Boundary::Wrap => grid.get(grid.wrap(point)),
+    };
+    let mut matches: Vec<(Vec<Point>, WordMatch)> = grid
+        .positions()
+        .par_bridge()
+        .flat_map_iter(|(start, _)| {
+            let letters = &letters;
+            config
+                .directions
+                .directions()
+                .iter()
+                .filter_map(move |&direction| {
+                    let found = WordMatch {
+                        start,
+                        direction,
+                        end: grid.wrap(start + direction.delta() * (letters.len() as isize - 1)),
+                        length: letters.len(),
+                    };
+                    found
+                        .cells()
+                        .zip(letters)
+                        .all(|(point, &letter)| {
+                            cell(point).is_some_and(|&c| config.case.matches(c, letter))
+                        })
+                        .then(|| (found.cells().map(|point| grid.wrap(point)).collect(), found))
+                })
+        })
+        .collect();
+    let directions = config.directions.directions();
+    let rank = |direction| directions.iter().position(|&d| d == direction);
+    matches.sort_unstable_by_key(|(_, found)| {
+        let direction = found.direction;
+        (
+            found.start.y,
+            found.start.x,
+            direction.is_diagonal(),
+            rank(direction),
+        )
     });
-    word_count.load(Ordering::Acquire)
+    let mut seen = HashSet::new();
+    matches
+        .into_iter()
+        .filter(|(cells, _)| seen.insert(cells.clone()))
+        .map(|(_, found)| found)
+        .collect()
+}
+
+/// How [`render_matches`] shows the letters outside of any match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Unmatched {
+    /// Replaced with `.`, as in the puzzle's illustration
+    #[default]
+    Dots,
+    /// Kept, but dimmed with ANSI escape codes
+    Dimmed,
 }
 
-fn count_x_mas_in_grid(grid: &Vec<Vec<char>>) -> u64 {
-    let x_mas_count = AtomicU64::new(0);
-    let n_cols = grid.len();
-    let n_rows = grid.get(0).expect("No rows").len();
-    grid.par_iter().enumerate().for_each(|(row_idx, col)| {
-        if col.len() != n_rows {
-            panic!("Not all rows have equal length")
+/// The grid with only the letters belonging to any of the matches visible.
+pub fn render_matches(grid: &Grid<char>, matches: &[WordMatch], unmatched: Unmatched) -> String {
+    let mut visible = grid.map(|_| false);
+    for cell in matches.iter().flat_map(WordMatch::cells) {
+        if let Some(visible) = visible.get_mut(visible.wrap(cell)) {
+            *visible = true;
         }
-        if !(row_idx < 1 || row_idx > n_rows - 2) {
-            col.par_iter().enumerate().for_each(|(col_idx, &c)| {
-                if !(col_idx < 1 || col_idx > (n_cols - 2)) {
-                    if c == 'A' {
-                        if ((grid[row_idx - 1][col_idx - 1] == 'M'
-                            && grid[row_idx + 1][col_idx + 1] == 'S')
-                            || (grid[row_idx - 1][col_idx - 1] == 'S'
-                                && grid[row_idx + 1][col_idx + 1] == 'M'))
-                            && ((grid[row_idx + 1][col_idx - 1] == 'M'
-                                && grid[row_idx - 1][col_idx + 1] == 'S')
-                                || (grid[row_idx + 1][col_idx - 1] == 'S'
-                                    && grid[row_idx - 1][col_idx + 1] == 'M'))
-                        {
-                            x_mas_count.fetch_add(1, Ordering::Relaxed);
-                        }
-                    }
+    }
+    let mut rendered = String::new();
+    for (y, row) in grid.rows().enumerate() {
+        for (&c, &visible) in row.iter().zip(visible.row(y)) {
+            match (visible, unmatched) {
+                (true, _) => rendered.push(c),
+                (false, Unmatched::Dots) => rendered.push('.'),
+                (false, Unmatched::Dimmed) => {
+                    rendered.extend([DIM, &c.to_string(), RESET]);
                 }
-            });
+            }
         }
-    });
-    x_mas_count.load(Ordering::Acquire)
+        rendered.push('\n');
+    }
+    rendered
 }
 
-pub fn day04() {
-    print!(r#"|@..#'. ~ " ' ~ |        |>O>o<@< \____       .'|  4: "#);
-    let file = read_to_string("src/data/day04.txt").expect("Unable to open file");
+/// Transformations under which a [`Template`] is matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    /// Only as written
+    None,
+    /// As written and mirrored from left to right
+    Mirror,
+    /// Turned by 0, 90, 180 and 270 degrees
+    Rotations,
+    /// All rotations of the template and of its mirror image
+    All,
+}
 
-    let mut grid: Vec<Vec<char>> = vec![];
-    for line in file.lines() {
-        grid.push(line.chars().collect())
+/// Small 2D pattern of characters, where `None` matches any cell
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Template {
+    cells: Grid<Option<char>>,
+}
+
+impl Template {
+    /// Parse a template written row by row, separated by `/`, with `.` as
+    /// wildcard, e.g. `M.S/.A./M.S`.
+    pub fn parse(pattern: &str) -> Result<Template, ParseError> {
+        let cells = Grid::parse(&pattern.replace('/', "\n"))?;
+        Ok(Template {
+            cells: cells.map(|&c| (c != '.').then_some(c)),
+        })
+    }
+
+    /// The distinct transformations of the template under the symmetry
+    pub fn variants(&self, symmetry: Symmetry) -> Vec<Template> {
+        let rotations = |template: &Template| {
+            let mut rotated = vec![template.clone()];
+            for _ in 0..3 {
+                let next = rotated.last().unwrap().cells.rotated();
+                rotated.push(Template { cells: next });
+            }
+            rotated
+        };
+        let mirror = Template {
+            cells: self.cells.mirrored(),
+        };
+        let mut variants = match symmetry {
+            Symmetry::None => vec![self.clone()],
+            Symmetry::Mirror => vec![self.clone(), mirror],
+            Symmetry::Rotations => rotations(self),
+            Symmetry::All => [rotations(self), rotations(&mirror)].concat(),
+        };
+        let mut seen = HashSet::new();
+        variants.retain(|variant| seen.insert(variant.clone()));
+        variants
+    }
+
+    /// Whether the template matches with its top left corner at `top_left`
+    pub fn matches_at(&self, grid: &Grid<char>, top_left: Point) -> bool {
+        self.cells.positions().all(|(offset, cell)| match cell {
+            None => grid.contains(top_left + offset),
+            Some(c) => grid.get(top_left + offset) == Some(c),
+        })
     }
-    let xmas_count = count_word_in_grid(&grid, "XMAS");
+}
+
+/// Number of places and orientations in which the template matches the grid.
+///
+/// Orientations that look the same are only counted once.
+pub fn count_template_in_grid(grid: &Grid<char>, template: &Template, symmetry: Symmetry) -> u64 {
+    let variants = template.variants(symmetry);
+    (0..grid.height() as isize)
+        .into_par_iter()
+        .map(|row_idx| {
+            (0..grid.width() as isize)
+                .map(|col_idx| Point::new(col_idx, row_idx))
+                .map(|top_left| {
+                    variants
+                        .iter()
+                        .filter(|variant| variant.matches_at(grid, top_left))
+                        .count() as u64
+                })
+                .sum::<u64>()
+        })
+        .sum()
+}
+
+/// Number of `A`s crossed by two diagonal `MAS`, read in any direction.
+pub fn count_x_mas_in_grid(grid: &Grid<char>) -> u64 {
+    let x_mas = Template::parse("M.S/.A./M.S").expect("Valid template");
+    count_template_in_grid(grid, &x_mas, Symmetry::Rotations)
+}
+
+pub struct Day04;
 
-    print!("XMAS count: {}, ", xmas_count);
+impl Solution for Day04 {
+    const DAY: u8 = 4;
+    const ART: &'static str = r#"|@..#'. ~ " ' ~ |        |>O>o<@< \____       .'| "#;
+    const INPUT: &'static str = "src/data/day04.txt";
+    const LABELS: [&'static str; 2] = ["XMAS count", "X-MAS count"];
 
-    let x_mas_count = count_x_mas_in_grid(&grid);
+    type Input = Grid<char>;
+    type PartOne = u64;
+    type PartTwo = u64;
 
-    println!("X-MAS count: {}", x_mas_count);
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        Grid::parse(input)
+    }
+
+    fn part_one(grid: &Self::Input) -> u64 {
+        count_word_in_grid(grid, "XMAS", &SearchConfig::STANDARD)
+    }
+
+    fn part_two(grid: &Self::Input) -> u64 {
+        count_x_mas_in_grid(grid)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::Location;
 
-    fn get_test_grid() -> Vec<Vec<char>> {
-        vec![
+    fn get_test_grid() -> Grid<char> {
+        Grid::from_rows(vec![
             vec!['M', 'M', 'M', 'S', 'X', 'X', 'M', 'A', 'S', 'M'],
             vec!['M', 'S', 'A', 'M', 'X', 'M', 'S', 'M', 'S', 'A'],
             vec!['A', 'M', 'X', 'S', 'X', 'M', 'A', 'A', 'M', 'M'],
@@ -161,16 +412,223 @@ mod tests {
             vec!['S', 'A', 'X', 'A', 'M', 'A', 'S', 'A', 'A', 'A'],
             vec!['M', 'A', 'M', 'M', 'M', 'X', 'M', 'M', 'M', 'M'],
             vec!['M', 'X', 'M', 'X', 'A', 'X', 'M', 'A', 'S', 'X'],
-        ]
+        ])
+        .unwrap()
     }
 
     #[test]
     fn test_count_word_in_grid() {
         let grid = get_test_grid();
-        let count = count_word_in_grid(&grid, "XMAS");
+        let count = count_word_in_grid(&grid, "XMAS", &SearchConfig::STANDARD);
         assert_eq!(count, 18);
     }
 
+    #[test]
+    fn test_count_words_in_grid() {
+        let grid = get_test_grid();
+        assert_eq!(
+            count_words_in_grid(&grid, &["XMAS", "SAMX", "MAS", "XMAS", "A", "", "QQ"]),
+            vec![18, 18, 38, 18, 24, 0, 0]
+        );
+        let palindromes = Grid::parse("ABA\nXXX\n").unwrap();
+        assert_eq!(
+            count_words_in_grid(&palindromes, &["ABA", "XX"]),
+            vec![2, 4]
+        );
+    }
+
+    fn get_wide_grid() -> Grid<char> {
+        Grid::parse("MMMSXXMASM\nMSAMXMSMSA\nAMXSXMAAMM\nMSAMASMSMX\n").unwrap()
+    }
+
+    fn get_tall_grid() -> Grid<char> {
+        Grid::parse("MMMS\nMSAM\nAMXS\nMSAM\nXMAS\nXXAM\nSMSM\nSAXA\nMAMM\nMXMX\n").unwrap()
+    }
+
+    #[test]
+    fn test_rectangular_grids() {
+        for grid in [get_wide_grid(), get_tall_grid()] {
+            assert_eq!(
+                count_word_in_grid(&grid, "XMAS", &SearchConfig::STANDARD),
+                3
+            );
+            assert_eq!(count_x_mas_in_grid(&grid), 3);
+            assert_eq!(
+                find_word_in_grid(&grid, "XMAS", &SearchConfig::STANDARD).len(),
+                3
+            );
+            // turning the grid neither loses nor adds any match
+            let rotated = grid.rotated();
+            assert_eq!(
+                count_word_in_grid(&rotated, "XMAS", &SearchConfig::STANDARD),
+                3
+            );
+            assert_eq!(count_x_mas_in_grid(&rotated), 3);
+        }
+        let row = Grid::parse("XMASAMX\n").unwrap();
+        assert_eq!(count_word_in_grid(&row, "XMAS", &SearchConfig::STANDARD), 2);
+        assert_eq!(
+            count_word_in_grid(&row.rotated(), "XMAS", &SearchConfig::STANDARD),
+            2
+        );
+        assert_eq!(count_x_mas_in_grid(&row), 0);
+    }
+
+    #[test]
+    fn test_parse_rejects_empty_grid() {
+        assert!(Day04::parse("\n").is_err());
+        assert!(Day04::parse("").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_ragged_grid() {
+        assert_eq!(
+            Day04::parse("XMAS\nXM\nXMAS\n"),
+            Err(ParseError::RaggedRow {
+                expected: 4,
+                found: 2,
+                at: Location::new(2, 3),
+            })
+        );
+    }
+
+    #[test]
+    fn test_find_word_in_grid() {
+        let grid = get_test_grid();
+        let matches = find_word_in_grid(&grid, "XMAS", &SearchConfig::STANDARD);
+        assert_eq!(matches.len(), 18);
+        assert_eq!(
+            matches[0],
+            WordMatch {
+                start: Point::new(4, 0),
+                direction: Dir8::DownRight,
+                end: Point::new(7, 3),
+                length: 4,
+            }
+        );
+        for word in ["MAS", "A", "SS"] {
+            assert_eq!(
+                find_word_in_grid(&grid, word, &SearchConfig::STANDARD).len() as u64,
+                count_word_in_grid(&grid, word, &SearchConfig::STANDARD)
+            );
+        }
+        assert!(find_word_in_grid(&grid, "", &SearchConfig::STANDARD).is_empty());
+    }
+
+    #[test]
+    fn test_search_config() {
+        let grid = get_test_grid();
+        let config = |boundary, directions, case| SearchConfig {
+            boundary,
+            directions,
+            case,
+        };
+        let count = |config: SearchConfig| count_word_in_grid(&grid, "XMAS", &config);
+        use {Boundary::*, Case::*, Directions::*};
+        assert_eq!(count(config(Clip, All, Sensitive)), 18);
+        let orthogonal = count(config(Clip, Orthogonal, Sensitive));
+        let diagonal = count(config(Clip, Diagonal, Sensitive));
+        assert_eq!((orthogonal, diagonal), (8, 10));
+        assert_eq!(count(config(Clip, Forward, Sensitive)), 6);
+        assert_eq!(count(config(Clip, All, Insensitive)), 18);
+        assert!(count(config(Wrap, All, Sensitive)) > 18);
+
+        let lower = grid.map(|c| c.to_ascii_lowercase());
+        let search = |word, config| count_word_in_grid(&lower, word, &config);
+        assert_eq!(search("XMAS", config(Clip, All, Sensitive)), 0);
+        assert_eq!(search("XMAS", config(Clip, All, Insensitive)), 18);
+        assert_eq!(search("xmas", SearchConfig::STANDARD), 18);
+    }
+
+    #[test]
+    fn test_wrapping_search() {
+        let wrap = SearchConfig {
+            boundary: Boundary::Wrap,
+            ..SearchConfig::STANDARD
+        };
+        let grid = Grid::parse("ASXM\n").unwrap();
+        assert_eq!(
+            count_word_in_grid(&grid, "XMAS", &SearchConfig::STANDARD),
+            0
+        );
+        let matches = find_word_in_grid(&grid, "XMAS", &wrap);
+        assert_eq!(
+            matches,
+            vec![WordMatch {
+                start: Point::new(2, 0),
+                direction: Dir8::Right,
+                end: Point::new(1, 0),
+                length: 4,
+            }]
+        );
+        assert_eq!(render_matches(&grid, &matches, Unmatched::Dots), "ASXM\n");
+        // reading right or left runs over the same cells of a two letter row
+        let row = Grid::parse("AB\n").unwrap();
+        let found = find_word_in_grid(&row, "ABAB", &wrap);
+        assert_eq!(
+            found
+                .iter()
+                .map(|found| (found.start, found.direction))
+                .collect::<Vec<_>>(),
+            vec![(Point::new(0, 0), Dir8::Right)]
+        );
+        // every direction of a single cell visits just that cell
+        let cell = Grid::parse("A\n").unwrap();
+        assert_eq!(count_word_in_grid(&cell, "AAA", &wrap), 1);
+        assert_eq!(count_word_in_grid(&cell, "AAA", &SearchConfig::STANDARD), 0);
+    }
+
+    #[test]
+    fn test_render_matches() {
+        let grid = Grid::parse("XMASX\nSAMXM\n").unwrap();
+        let matches = find_word_in_grid(&grid, "XMAS", &SearchConfig::STANDARD);
+        assert_eq!(
+            matches
+                .iter()
+                .map(|found| (found.start, found.direction))
+                .collect::<Vec<_>>(),
+            vec![
+                (Point::new(0, 0), Dir8::Right),
+                (Point::new(3, 1), Dir8::Left)
+            ]
+        );
+        assert_eq!(
+            render_matches(&grid, &matches, Unmatched::Dots),
+            "XMAS.\nSAMX.\n"
+        );
+        let dimmed = render_matches(&grid, &matches[..1], Unmatched::Dimmed);
+        assert!(dimmed.starts_with(&format!("XMAS{DIM}X{RESET}\n{DIM}S{RESET}")));
+    }
+
+    #[test]
+    fn test_template_variants() {
+        let x_mas = Template::parse("M.S/.A./M.S").unwrap();
+        assert_eq!(x_mas.variants(Symmetry::None).len(), 1);
+        assert_eq!(x_mas.variants(Symmetry::Rotations).len(), 4);
+        // mirroring the X only yields rotations of it
+        assert_eq!(x_mas.variants(Symmetry::All).len(), 4);
+        let plus = Template::parse(".A./AAA/.A.").unwrap();
+        assert_eq!(plus.variants(Symmetry::All).len(), 1);
+        let corner = Template::parse("AB/C.").unwrap();
+        assert_eq!(corner.variants(Symmetry::All).len(), 8);
+        assert!(Template::parse("AB/C").is_err());
+    }
+
+    #[test]
+    fn test_count_template_in_grid() {
+        let grid = Grid::parse("SAS\nAAA\nMAM\n").unwrap();
+        let plus = Template::parse(".A./AAA/.A.").unwrap();
+        assert_eq!(count_template_in_grid(&grid, &plus, Symmetry::All), 1);
+        let square = Template::parse("AA/AA").unwrap();
+        assert_eq!(count_template_in_grid(&grid, &square, Symmetry::None), 0);
+        let line = Template::parse("SAS").unwrap();
+        assert_eq!(count_template_in_grid(&grid, &line, Symmetry::None), 1);
+        assert_eq!(count_template_in_grid(&grid, &line, Symmetry::Rotations), 1);
+        let pair = Template::parse("MA").unwrap();
+        assert_eq!(count_template_in_grid(&grid, &pair, Symmetry::Mirror), 2);
+        assert_eq!(count_template_in_grid(&grid, &pair, Symmetry::All), 4);
+    }
+
     #[test]
     fn test_count_x_mas_in_grid() {
         let grid = get_test_grid();
@@ -1,24 +1,29 @@
+use super::Solution;
+use crate::error::{parse_number, Location, ParseError};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-use std::fs::read_to_string;
+use std::collections::{BTreeSet, HashMap};
 
-fn sum_middle_pages(updates: &Vec<Vec<u32>>) -> u64 {
+/// Sum of the middle page numbers of the updates.
+pub fn sum_middle_pages(updates: &[Vec<u32>]) -> u64 {
     updates
         .par_iter()
         .map(|update| update[update.len() / 2] as u64)
         .sum()
 }
 
-fn split_ordered_updates(
-    rules: &Vec<(u32, u32)>,
-    updates: &Vec<Vec<u32>>,
+/// Split the updates into those already in the right order and the others.
+pub fn split_ordered_updates(
+    rules: &[(u32, u32)],
+    updates: &[Vec<u32>],
 ) -> (Vec<Vec<u32>>, Vec<Vec<u32>>) {
     updates
         .par_iter()
         .cloned()
-        .partition(|update| validate_update_order(&rules, &update))
+        .partition(|update| validate_update_order(rules, update))
 }
 
-fn validate_update_order(rules: &Vec<(u32, u32)>, update: &Vec<u32>) -> bool {
+/// Whether no rule puts a later page of the update before an earlier one.
+pub fn validate_update_order(rules: &[(u32, u32)], update: &[u32]) -> bool {
     !rules.par_iter().any(|rule| {
         if update.contains(&rule.0) && update.contains(&rule.1) {
             let rule_1_index = update.iter().position(|&page| page == rule.0).unwrap();
@@ -30,65 +35,114 @@ fn validate_update_order(rules: &Vec<(u32, u32)>, update: &Vec<u32>) -> bool {
     })
 }
 
-fn correct_update_order(rules: &Vec<(u32, u32)>, mut update: Vec<u32>) -> Vec<u32> {
-    while !validate_update_order(&rules, &update) {
-        rules.iter().for_each(|rule| {
-            if update.contains(&rule.0) && update.contains(&rule.1) {
-                let rule_1_index = update.iter().position(|&page| page == rule.0).unwrap();
-                let rule_2_index = update.iter().position(|&page| page == rule.1).unwrap();
-                if rule_1_index > rule_2_index {
-                    update.swap(rule_1_index, rule_2_index);
+/// Reorder the update so that it satisfies all rules, by sorting its pages topologically.
+///
+/// Only rules between two pages of the update are taken into account. Ties
+/// are broken deterministically in favour of the page coming first in the
+/// given update: among the pages whose predecessors are all placed, the
+/// earliest one is placed next. Pages that no rule orders relative to each
+/// other thus keep their order, and an update that already satisfies all
+/// rules is returned unchanged. Should the rules form a cycle among the
+/// pages, the earliest page not yet placed is taken regardless of its
+/// remaining predecessors, so that the order stays as close to the rules as
+/// the cycle allows.
+pub fn correct_update_order(rules: &[(u32, u32)], update: Vec<u32>) -> Vec<u32> {
+    let mut indices: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (idx, &page) in update.iter().enumerate() {
+        indices.entry(page).or_default().push(idx);
+    }
+    let mut successors = vec![vec![]; update.len()];
+    let mut predecessor_counts = vec![0; update.len()];
+    for (before, after) in rules {
+        if let (Some(befores), Some(afters)) = (indices.get(before), indices.get(after)) {
+            for &before_idx in befores {
+                for &after_idx in afters {
+                    successors[before_idx].push(after_idx);
+                    predecessor_counts[after_idx] += 1;
                 }
             }
-        });
+        }
+    }
+    let mut ready: BTreeSet<usize> = (0..update.len())
+        .filter(|&idx| predecessor_counts[idx] == 0)
+        .collect();
+    let mut remaining: BTreeSet<usize> = (0..update.len()).collect();
+    let mut corrected = Vec::with_capacity(update.len());
+    while let Some(&idx) = ready.first().or_else(|| remaining.first()) {
+        ready.remove(&idx);
+        remaining.remove(&idx);
+        corrected.push(update[idx]);
+        for &successor in &successors[idx] {
+            predecessor_counts[successor] -= 1;
+            if predecessor_counts[successor] == 0 && remaining.contains(&successor) {
+                ready.insert(successor);
+            }
+        }
     }
-    update
+    corrected
 }
 
-pub fn day05() {
-    print!(r"|_.~._#'.. ~ ~ *|        | _| |_    ..\_\_ ..'* |  5: ");
-    let file = read_to_string("src/data/day05.txt").expect("Unable to open file");
-    let mut rules: Vec<(u32, u32)> = vec![];
-    let mut updates: Vec<Vec<u32>> = vec![];
-    let mut empty_line_found = false;
-    for line in file.lines().into_iter() {
-        if line.is_empty() {
-            empty_line_found = true
-        } else if !empty_line_found {
-            let parts: Vec<&str> = line.split("|").collect();
-            let page_1: u32 = parts[0].parse().expect("Failed to parse page 1");
-            let page_2: u32 = parts[1].parse().expect("Failed to parse page 2");
-            rules.push((page_1, page_2))
-        } else {
-            updates.push(
-                line.split(",")
-                    .collect::<Vec<&str>>()
-                    .into_iter()
-                    .map(|page| {
-                        page.parse()
-                            .expect(&format!("Failed to parse page {} in line {}", page, line))
-                    })
-                    .collect::<Vec<u32>>(),
-            );
+pub struct Day05;
+
+impl Solution for Day05 {
+    const DAY: u8 = 5;
+    const ART: &'static str = r"|_.~._#'.. ~ ~ *|        | _| |_    ..\_\_ ..'* | ";
+    const INPUT: &'static str = "src/data/day05.txt";
+    const LABELS: [&'static str; 2] = [
+        "Middle sum of already valid updates 1",
+        "Middle sum of remaining corrected updates 2",
+    ];
+
+    type Input = (Vec<(u32, u32)>, Vec<Vec<u32>>);
+    type PartOne = u64;
+    type PartTwo = u64;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        let mut rules: Vec<(u32, u32)> = vec![];
+        let mut updates: Vec<Vec<u32>> = vec![];
+        let mut empty_line_found = false;
+        for (line_idx, line) in input.lines().enumerate() {
+            if line.is_empty() {
+                empty_line_found = true
+            } else if !empty_line_found {
+                let (page_1, page_2) = line.split_once("|").ok_or(ParseError::Missing {
+                    expected: "`|` between the pages of a rule",
+                    at: Location::new(line_idx + 1, line.chars().count() + 1),
+                })?;
+                rules.push((
+                    parse_number(line_idx, line, page_1)?,
+                    parse_number(line_idx, line, page_2)?,
+                ))
+            } else {
+                updates.push(
+                    line.split(",")
+                        .map(|page| parse_number(line_idx, line, page))
+                        .collect::<Result<Vec<u32>, _>>()?,
+                );
+            }
         }
+        Ok((rules, updates))
+    }
+
+    fn part_one((rules, updates): &Self::Input) -> u64 {
+        let (ordered_updates, _) = split_ordered_updates(rules, updates);
+        sum_middle_pages(&ordered_updates)
+    }
+
+    fn part_two((rules, updates): &Self::Input) -> u64 {
+        let (_, unordered_updates) = split_ordered_updates(rules, updates);
+        let corrected_updates: Vec<Vec<u32>> = unordered_updates
+            .par_iter()
+            .map(|update| correct_update_order(rules, update.to_owned()))
+            .collect();
+        sum_middle_pages(&corrected_updates)
     }
-    let (ordered_updates, unordered_updates) = split_ordered_updates(&rules, &updates);
-    let result_task_1 = sum_middle_pages(&ordered_updates);
-    print!("Middle sum of already valid updates 1: {}, ", result_task_1);
-    let corrected_updates: Vec<Vec<u32>> = unordered_updates
-        .par_iter()
-        .map(|update| correct_update_order(&rules, update.to_owned()))
-        .collect();
-    let result_task_2 = sum_middle_pages(&corrected_updates);
-    println!(
-        "Middle sum of remaining corrected updates 2: {}",
-        result_task_2
-    );
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::rng::XorShift;
     fn get_test_rules() -> Vec<(u32, u32)> {
         vec![
             (47, 53),
@@ -153,7 +207,7 @@ mod tests {
         let test_validities = get_test_validities();
         let computed_validites = updates
             .iter()
-            .map(|update| validate_update_order(&rules, &update))
+            .map(|update| validate_update_order(&rules, update))
             .collect::<Vec<_>>();
         assert_eq!(test_validities, computed_validites)
     }
@@ -191,6 +245,60 @@ mod tests {
         assert_eq!(test_corrected_updates, corrected_updates);
     }
 
+    #[test]
+    fn test_correct_update_order_tie_breaking() {
+        let rules = get_test_rules();
+        // already valid updates are left as they are
+        for update in &get_test_updates()[..3] {
+            assert_eq!(&correct_update_order(&rules, update.clone()), update);
+        }
+        // pages without rules between them keep their relative order
+        let rules = vec![(1, 2)];
+        assert_eq!(
+            correct_update_order(&rules, vec![9, 2, 5, 1, 3]),
+            vec![9, 5, 1, 2, 3]
+        );
+        assert_eq!(correct_update_order(&rules, vec![3, 2, 4]), vec![3, 2, 4]);
+    }
+
+    #[test]
+    fn test_correct_update_order_with_cycle() {
+        // 1 -> 2 -> 3 -> 1, broken at the earliest page of the cycle in the update
+        let rules = vec![(1, 2), (2, 3), (3, 1), (4, 3)];
+        assert_eq!(
+            correct_update_order(&rules, vec![3, 2, 1, 4]),
+            vec![4, 3, 1, 2]
+        );
+        assert_eq!(
+            correct_update_order(&rules, vec![4, 1, 2, 3]),
+            vec![4, 1, 2, 3]
+        );
+    }
+
+    #[test]
+    fn test_correct_update_order_random() {
+        let mut rng = XorShift::new(0x2024_0005);
+        for _ in 0..200 {
+            // rules consistent with the order of the page numbers
+            let rules: Vec<(u32, u32)> = (0..30)
+                .map(|_| (rng.below(20) as u32, rng.below(20) as u32))
+                .filter(|(a, b)| a < b)
+                .collect();
+            let mut update: Vec<u32> = (0..20).collect();
+            for idx in (1..update.len()).rev() {
+                update.swap(idx, rng.below(idx + 1));
+            }
+            update.truncate(rng.below(20) + 1);
+            let corrected = correct_update_order(&rules, update.clone());
+            assert!(validate_update_order(&rules, &corrected));
+            let mut sorted = update.clone();
+            sorted.sort_unstable();
+            let mut corrected_sorted = corrected.clone();
+            corrected_sorted.sort_unstable();
+            assert_eq!(sorted, corrected_sorted);
+        }
+    }
+
     #[test]
     fn test_sum_middle_pages_with_reordering() {
         let corrected_updates = get_corrected_updates();
@@ -1,195 +1,122 @@
-use rayon::prelude::*;
-use std::sync::{Arc, Mutex};
-use std::time::Instant;
-use std::{
-    collections::HashSet,
-    fs::read_to_string,
-    sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+use super::Solution;
+use crate::{
+    error::{Location, ParseError},
+    utils::{Dir4, Grid, Point},
 };
-
-#[derive(PartialEq, Eq, Hash, Clone, Copy)]
-enum Direction {
-    Up,
-    Right,
-    Down,
-    Left,
-}
-
-impl Direction {
-    fn next_move(&self) -> (i32, i32) {
-        match self {
-            Direction::Up => (-1, 0),
-            Direction::Right => (0, 1),
-            Direction::Down => (1, 0),
-            Direction::Left => (0, -1),
-        }
-    }
-
-    fn turn(&mut self) {
-        *self = match self {
-            Direction::Up => Direction::Right,
-            Direction::Right => Direction::Down,
-            Direction::Down => Direction::Left,
-            Direction::Left => Direction::Up,
-        }
-    }
-}
-
-impl From<Direction> for char {
-    fn from(direction: Direction) -> char {
-        match direction {
-            Direction::Up => '^',
-            Direction::Right => '>',
-            Direction::Down => 'v',
-            Direction::Left => '<',
-        }
-    }
-}
-
-impl From<char> for Direction {
-    fn from(c: char) -> Direction {
-        match c {
-            '^' => Direction::Up,
-            '>' => Direction::Right,
-            'v' => Direction::Down,
-            '<' => Direction::Left,
-            _ => panic!("Invalid direction character: {}", c),
-        }
-    }
-}
-
-fn get_guard_state(map: &Vec<Vec<char>>) -> (i32, i32, Direction) {
-    let stop = AtomicBool::new(false);
-    let a_row_idx = AtomicUsize::new(0);
-    let a_col_idx = AtomicUsize::new(0);
-    let direction = Arc::new(Mutex::new('X'));
-    map.par_iter().enumerate().for_each(|(row_idx, row)| {
-        row.par_iter().enumerate().for_each(|(col_idx, &cell)| {
-            if stop.load(Ordering::Relaxed) {
-                return;
-            }
-            if "^>v<".contains(cell) {
-                stop.store(true, Ordering::Relaxed);
-                a_row_idx.store(row_idx, Ordering::Relaxed);
-                a_col_idx.store(col_idx, Ordering::Relaxed);
-                let mut direction_ref = direction.lock().unwrap();
-                *direction_ref = cell;
-            }
-        })
-    });
-    let guard_direction = *direction.lock().unwrap();
-    (
-        a_row_idx.load(Ordering::Relaxed) as i32,
-        a_col_idx.load(Ordering::Relaxed) as i32,
-        Direction::from(guard_direction),
-    )
+use rayon::prelude::*;
+use std::collections::HashSet;
+
+/// Position and direction of the guard
+pub type Guard = (Point, Dir4);
+
+/// Position and direction of the guard, if there is one on the map.
+pub fn get_guard_state(map: &Grid<char>) -> Option<Guard> {
+    map.positions().find_map(|(position, &cell)| {
+        Dir4::try_from(cell)
+            .ok()
+            .map(|direction| (position, direction))
+    })
 }
 
-fn note_visited_fields(map: &mut Vec<Vec<char>>) -> Vec<Vec<char>> {
-    let (mut row_idx, mut col_idx, mut direction) = get_guard_state(map);
+/// Copy of the map with every field visited by the guard marked with `X`.
+pub fn note_visited_fields(map: &Grid<char>, guard: Guard) -> Grid<char> {
+    let (mut position, mut direction) = guard;
     let mut map_notes = map.clone();
     loop {
-        map_notes[row_idx as usize][col_idx as usize] = 'X';
-        let (row_move, col_move) = direction.next_move();
-        let new_row_idx = (row_idx + row_move) as usize;
-        let new_col_idx = (col_idx + col_move) as usize;
-
-        if let Some(row) = map.get(new_row_idx) {
-            if let Some(&cell) = row.get(new_col_idx) {
-                if cell == '#' {
-                    direction.turn();
-                } else {
-                    row_idx += row_move;
-                    col_idx += col_move;
-                }
-                continue;
-            }
+        *map_notes.get_mut(position).unwrap() = 'X';
+        match map.get(position + direction.delta()) {
+            Some('#') => direction = direction.turn_right(),
+            Some(_) => position += direction.delta(),
+            None => break,
         }
-        break;
     }
-    // for row in &map_notes {
-    //     println!("{}", row.iter().collect::<String>());
-    // }
     map_notes
 }
 
-fn sum_visited_fields(map: &Vec<Vec<char>>) -> u64 {
-    let map_notes = note_visited_fields(&mut map.clone());
-    map_notes
-        .par_iter()
-        .flatten()
-        .filter(|&&c| c == 'X')
-        .count() as u64
+/// Number of distinct fields the guard visits before leaving the map.
+pub fn sum_visited_fields(map: &Grid<char>, guard: Guard) -> u64 {
+    let map_notes = note_visited_fields(map, guard);
+    map_notes.cells().iter().filter(|&&c| c == 'X').count() as u64
 }
 
 /// determine if the guard is in a loop by checking whether the guard
 /// - leaves the map eventually
 /// - returns to the same position with the same direction at any point
-fn is_looping(map: &Vec<Vec<char>>, obstacle_position: (usize, usize)) -> bool {
-    let (mut row_idx, mut col_idx, mut direction) = get_guard_state(&map);
-    let mut visited_positions_directions: HashSet<(i32, i32, Direction)> = HashSet::new();
+pub fn is_looping(map: &Grid<char>, guard: Guard, obstacle_position: Point) -> bool {
+    let (mut position, mut direction) = guard;
+    let mut visited_positions_directions: HashSet<(Point, Dir4)> = HashSet::new();
     loop {
-        if visited_positions_directions.contains(&(row_idx, col_idx, direction)) {
+        if !visited_positions_directions.insert((position, direction)) {
             return true;
         }
-        visited_positions_directions.insert((row_idx, col_idx, direction));
-        let (row_move, col_move) = direction.next_move();
-        let new_row_idx = (row_idx + row_move) as usize;
-        let new_col_idx = (col_idx + col_move) as usize;
-
-        if let Some(row) = map.get(new_row_idx) {
-            if let Some(&cell) = row.get(new_col_idx) {
-                if cell == '#' || obstacle_position == (new_row_idx, new_col_idx) {
-                    direction.turn();
-                } else {
-                    row_idx += row_move;
-                    col_idx += col_move;
-                }
-                continue;
-            }
+        let new_position = position + direction.delta();
+        match map.get(new_position) {
+            Some('#') => direction = direction.turn_right(),
+            Some(_) if obstacle_position == new_position => direction = direction.turn_right(),
+            Some(_) => position = new_position,
+            None => return false,
         }
-        break;
     }
-    false
 }
 
-fn get_number_of_looping_obstacle_locations(map: &Vec<Vec<char>>) -> u64 {
-    let map_notes = note_visited_fields(&mut map.clone());
-    let (guard_row_idx, guard_col_idx, _direction) = get_guard_state(&map);
-    let obstacle_location_count = AtomicU64::new(0);
-    map_notes.par_iter().enumerate().for_each(|(row_idx, row)| {
-        row.par_iter().enumerate().for_each(|(col_idx, &c)| {
-            if c == 'X' && !(row_idx == guard_row_idx as usize && col_idx == guard_col_idx as usize)
-            {
-                if is_looping(&map, (row_idx, col_idx)) {
-                    obstacle_location_count.fetch_add(1, Ordering::Relaxed);
-                }
-            }
+/// Number of fields where a single new obstacle traps the guard in a loop.
+pub fn get_number_of_looping_obstacle_locations(map: &Grid<char>, guard: Guard) -> u64 {
+    let map_notes = note_visited_fields(map, guard);
+    let (guard_position, _direction) = guard;
+    map_notes
+        .positions()
+        .par_bridge()
+        .filter(|&(position, &c)| {
+            c == 'X' && position != guard_position && is_looping(map, guard, position)
         })
-    });
-    obstacle_location_count.load(Ordering::Acquire)
+        .count() as u64
 }
 
-pub fn day06() {
-    print!(r"| ||| @@##'''...|        |...     .'  '.'''../..|  6: ");
-    let file = read_to_string("src/data/day06.txt").expect("Unable to open file");
-    let map: Vec<Vec<char>> = file.lines().map(|line| line.chars().collect()).collect();
-    print!("Visited fields: {}, ", sum_visited_fields(&map));
-    let start = Instant::now();
-    let number_of_obstacle_locations = get_number_of_looping_obstacle_locations(&map);
-    let duration = start.elapsed();
-    println!(
-        "{} possible obstacle locations found in {:?}ms", // release time on M2 Mac: ~275ms
-        number_of_obstacle_locations, duration
-    );
+pub struct Day06;
+
+impl Solution for Day06 {
+    const DAY: u8 = 6;
+    const ART: &'static str = r"| ||| @@##'''...|        |...     .'  '.'''../..| ";
+    const INPUT: &'static str = "src/data/day06.txt";
+    const LABELS: [&'static str; 2] = ["Visited fields", "possible obstacle locations"];
+
+    type Input = (Grid<char>, Guard);
+    type PartOne = u64;
+    type PartTwo = u64;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        let map = Grid::parse(input)?;
+        if let Some((position, &cell)) = map
+            .positions()
+            .find(|(_, &cell)| !".#".contains(cell) && Dir4::try_from(cell).is_err())
+        {
+            return Err(ParseError::InvalidChar {
+                found: cell,
+                at: Location::new(position.y as usize + 1, position.x as usize + 1),
+            });
+        }
+        let guard = get_guard_state(&map).ok_or(ParseError::Missing {
+            expected: "guard",
+            at: Location::new(1, 1),
+        })?;
+        Ok((map, guard))
+    }
+
+    fn part_one((map, guard): &Self::Input) -> u64 {
+        sum_visited_fields(map, *guard)
+    }
+
+    fn part_two((map, guard): &Self::Input) -> u64 {
+        get_number_of_looping_obstacle_locations(map, *guard)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn get_map() -> Vec<Vec<char>> {
-        vec![
+    fn get_map() -> Grid<char> {
+        Grid::from_rows(vec![
             vec!['.', '.', '.', '.', '#', '.', '.', '.', '.', '.'],
             vec!['.', '.', '.', '.', '.', '.', '.', '.', '.', '#'],
             vec!['.', '.', '.', '.', '.', '.', '.', '.', '.', '.'],
@@ -200,18 +127,41 @@ mod tests {
             vec!['.', '.', '.', '.', '.', '.', '.', '.', '#', '.'],
             vec!['#', '.', '.', '.', '.', '.', '.', '.', '.', '.'],
             vec!['.', '.', '.', '.', '.', '.', '#', '.', '.', '.'],
-        ]
+        ])
+        .unwrap()
     }
 
     #[test]
     fn test_sum_visited_fields() {
         let map = get_map();
-        assert_eq!(sum_visited_fields(&map), 41);
+        let guard = (Point::new(4, 6), Dir4::Up);
+        assert_eq!(get_guard_state(&map), Some(guard));
+        assert_eq!(sum_visited_fields(&map, guard), 41);
     }
 
     #[test]
     fn test_get_number_of_obstacle_locations() {
         let map = get_map();
-        assert_eq!(get_number_of_looping_obstacle_locations(&map), 6);
+        let guard = get_guard_state(&map).unwrap();
+        assert_eq!(get_number_of_looping_obstacle_locations(&map, guard), 6);
+        assert!(is_looping(&map, guard, Point::new(3, 6)));
+        assert!(!is_looping(&map, guard, Point::new(0, 0)));
+    }
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Day06::parse("..#\n.x.\n"),
+            Err(ParseError::InvalidChar {
+                found: 'x',
+                at: Location::new(2, 2),
+            })
+        );
+        assert_eq!(
+            Day06::parse("..#\n...\n"),
+            Err(ParseError::Missing {
+                expected: "guard",
+                at: Location::new(1, 1),
+            })
+        );
     }
 }
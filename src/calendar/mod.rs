@@ -1,3 +1,11 @@
+use crate::error::ParseError;
+use std::{
+    fmt::{self, Display},
+    hint::black_box,
+    io::BufRead,
+    time::{Duration, Instant},
+};
+
 pub mod day01;
 pub mod day02;
 pub mod day03;
@@ -5,9 +13,216 @@ pub mod day04;
 pub mod day05;
 pub mod day06;
 
-pub use day01::day01;
-pub use day02::day02;
-pub use day03::day03;
-pub use day04::day04;
-pub use day05::day05;
-pub use day06::day06;
+/// A puzzle of the calendar: how to read its input and how to answer both parts.
+pub trait Solution {
+    /// Day of the calendar the puzzle belongs to
+    const DAY: u8;
+    /// ASCII-art line of the calendar printed in front of the answers
+    const ART: &'static str;
+    /// Path of the puzzle input
+    const INPUT: &'static str;
+    /// Descriptions printed in front of the answers of part one and two
+    const LABELS: [&'static str; 2];
+
+    type Input;
+    type PartOne: Display;
+    type PartTwo: Display;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError>;
+    fn part_one(input: &Self::Input) -> Self::PartOne;
+    fn part_two(input: &Self::Input) -> Self::PartTwo;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    pub const BOTH: [Part; 2] = [Part::One, Part::Two];
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Part::One => write!(f, "1"),
+            Part::Two => write!(f, "2"),
+        }
+    }
+}
+
+/// Answer of one part, rendered for printing, and the time it took to compute
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Answer {
+    pub part: Part,
+    pub value: String,
+    pub duration: Duration,
+}
+
+pub type Answers = Vec<Answer>;
+
+/// Durations of repeated runs of each phase of a solution
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Timings {
+    pub parse: Vec<Duration>,
+    pub part_one: Vec<Duration>,
+    pub part_two: Vec<Duration>,
+}
+
+/// Settings of the runner that change how some days read and answer their input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Options {
+    /// How day 3 treats the line breaks of its memory
+    pub newlines: day03::Newlines,
+    /// Number of digits day 3 accepts for the operands of `mul`
+    pub digits: day03::Digits,
+    /// Let day 3 sum with integers of any size instead of failing on overflow
+    pub big_int: bool,
+}
+
+/// Answers computed from the whole input, taking the options into account
+pub type SolveFn = fn(&str, &[Part], &Options) -> Result<Answers, ParseError>;
+/// Answers computed straight from a reader, for days able to stream their input
+pub type StreamFn = fn(&mut dyn BufRead, &[Part], &Options) -> Result<Answers, ParseError>;
+
+/// Type-erased entry of the registry, created from a [`Solution`].
+pub struct Day {
+    pub number: u8,
+    pub art: &'static str,
+    pub input: &'static str,
+    pub labels: [&'static str; 2],
+    solve: SolveFn,
+    bench: fn(&str, usize) -> Result<Timings, ParseError>,
+    stream: Option<StreamFn>,
+}
+
+impl Day {
+    const fn new<S: Solution>() -> Day {
+        Day {
+            number: S::DAY,
+            art: S::ART,
+            input: S::INPUT,
+            labels: S::LABELS,
+            solve: solve::<S>,
+            bench: bench::<S>,
+            stream: None,
+        }
+    }
+
+    /// Let the day answer according to the options instead of ignoring them.
+    const fn with_options(self, solve: SolveFn) -> Day {
+        Day { solve, ..self }
+    }
+
+    /// Let the day answer from a reader, without loading the whole input.
+    const fn with_stream(self, stream: StreamFn) -> Day {
+        Day {
+            stream: Some(stream),
+            ..self
+        }
+    }
+
+    /// Parse the input once and answer the requested parts in the given order.
+    pub fn solve(
+        &self,
+        input: &str,
+        parts: &[Part],
+        options: &Options,
+    ) -> Result<Answers, ParseError> {
+        (self.solve)(input, parts, options)
+    }
+
+    /// Whether the day can answer while reading its input line by line
+    pub fn can_stream(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Answer the requested parts while reading the input line by line, or
+    /// `None` if the day needs its input as a whole.
+    pub fn solve_stream(
+        &self,
+        reader: &mut dyn BufRead,
+        parts: &[Part],
+        options: &Options,
+    ) -> Option<Result<Answers, ParseError>> {
+        self.stream.map(|stream| stream(reader, parts, options))
+    }
+
+    /// Time parsing the input and answering both parts, `runs` times each.
+    pub fn bench(&self, input: &str, runs: usize) -> Result<Timings, ParseError> {
+        (self.bench)(input, runs)
+    }
+
+    pub fn label(&self, part: Part) -> &'static str {
+        match part {
+            Part::One => self.labels[0],
+            Part::Two => self.labels[1],
+        }
+    }
+}
+
+fn solve<S: Solution>(input: &str, parts: &[Part], _: &Options) -> Result<Answers, ParseError> {
+    Ok(answer::<S>(&S::parse(input)?, parts))
+}
+
+/// Answer the requested parts from an already parsed input.
+fn answer<S: Solution>(input: &S::Input, parts: &[Part]) -> Answers {
+    parts
+        .iter()
+        .map(|&part| {
+            let start = Instant::now();
+            let value = match part {
+                Part::One => S::part_one(input).to_string(),
+                Part::Two => S::part_two(input).to_string(),
+            };
+            Answer {
+                part,
+                value,
+                duration: start.elapsed(),
+            }
+        })
+        .collect()
+}
+
+fn bench<S: Solution>(input: &str, runs: usize) -> Result<Timings, ParseError> {
+    let mut timings = Timings::default();
+    for _ in 0..runs {
+        let start = Instant::now();
+        let parsed = S::parse(black_box(input))?;
+        timings.parse.push(start.elapsed());
+        let start = Instant::now();
+        black_box(S::part_one(&parsed));
+        timings.part_one.push(start.elapsed());
+        let start = Instant::now();
+        black_box(S::part_two(&parsed));
+        timings.part_two.push(start.elapsed());
+    }
+    Ok(timings)
+}
+
+/// All solved days, in calendar order.
+pub const DAYS: &[Day] = &[
+    Day::new::<day01::Day01>().with_stream(day01::solve_stream),
+    Day::new::<day02::Day02>().with_stream(day02::solve_stream),
+    Day::new::<day03::Day03>()
+        .with_options(day03::solve_with)
+        .with_stream(day03::solve_stream),
+    Day::new::<day04::Day04>(),
+    Day::new::<day05::Day05>(),
+    Day::new::<day06::Day06>(),
+];
+
+pub fn get(number: u8) -> Option<&'static Day> {
+    DAYS.iter().find(|day| day.number == number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_days_are_unique_and_in_order() {
+        assert!(DAYS.windows(2).all(|pair| pair[0].number < pair[1].number));
+    }
+}
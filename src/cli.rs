@@ -0,0 +1,402 @@
+use advent_of_code_2024::{
+    answers,
+    calendar::{self, Day, Options, Part},
+    report::Format,
+};
+use std::{
+    fmt,
+    fs::{read_to_string, File},
+    io::{self, BufRead, BufReader, Read},
+    path::PathBuf,
+};
+
+pub const USAGE: &str = "\
+Usage: advent_of_code_2024 [COMMAND] [OPTIONS]
+
+Commands:
+  run               Print the answers of the selected puzzles (default)
+  verify            Compare the answers against the known answers
+  bench             Time parsing and both parts of the selected puzzles
+  diagnose          List the unsafe reports of day 2 with the level breaking them
+  trace             List the instructions of day 3 as they are run
+  new-day <N>       Generate the module, input files and registry entry of day N
+
+Options:
+  --day <N>         Run a single day
+  --days <RANGE>    Run several days, e.g. `1-6` or `1,3,5`
+  --part <1|2>      Only answer one part of the puzzle
+  --input <PATH>    Read the puzzle input from PATH, or from stdin with `-`
+  --format <FORMAT> Output format: calendar (default), json, csv or junit
+  --stream          Let `run` read the input line by line where the day supports it
+  --newlines <MODE> How day 3 treats line breaks: memory (default), where they break
+                    up instructions, or join, where lines are joined as they used to be
+  --digits <N>      Let day 3 accept `mul` operands of up to N digits (default: 3, at most 19)
+  --big-int         Let day 3 sum with integers of any size instead of failing on overflow
+  --answers <PATH>  Known answers for `verify` (default: answers.toml)
+  --record          Let `verify` store answers for inputs without known answers
+  --runs <N>        Number of `bench` runs per puzzle (default: 10)
+  --save <PATH>     Save the `bench` timings as a baseline
+  --compare <PATH>  Compare the `bench` timings against a saved baseline
+  --threshold <PCT> Slowdown of the median flagged as regression (default: 10)
+  --highlight       Let `trace` print the memory with the enabled regions coloured
+  -h, --help        Print this help";
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Command {
+    #[default]
+    Run,
+    Verify {
+        answers: PathBuf,
+        record: bool,
+    },
+    Bench {
+        runs: usize,
+        save: Option<PathBuf>,
+        compare: Option<PathBuf>,
+        /// Slowdown of the median, in percent, reported as regression
+        threshold: f64,
+    },
+    Diagnose,
+    Trace {
+        highlight: bool,
+    },
+    NewDay {
+        day: u8,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    File(PathBuf),
+    Stdin,
+}
+
+impl InputSource {
+    pub fn read(&self) -> io::Result<String> {
+        match self {
+            InputSource::File(path) => read_to_string(path),
+            InputSource::Stdin => {
+                let mut input = String::new();
+                io::stdin().read_to_string(&mut input)?;
+                Ok(input)
+            }
+        }
+    }
+
+    /// Open the input for reading it piece by piece.
+    pub fn reader(&self) -> io::Result<Box<dyn BufRead>> {
+        Ok(match self {
+            InputSource::File(path) => Box::new(BufReader::new(File::open(path)?)),
+            InputSource::Stdin => Box::new(io::stdin().lock()),
+        })
+    }
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputSource::File(path) => write!(f, "{}", path.display()),
+            InputSource::Stdin => write!(f, "<stdin>"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Args {
+    pub command: Command,
+    /// Selected days, all registered days if empty
+    pub days: Vec<u8>,
+    /// Selected parts, both parts if empty
+    pub parts: Vec<Part>,
+    /// Replacement for the default input of the selected day
+    pub input: Option<InputSource>,
+    pub format: Format,
+    /// Read the input line by line instead of loading it as a whole
+    pub stream: bool,
+    pub options: Options,
+    pub help: bool,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter().peekable();
+        let command = args.next_if(|arg| !arg.starts_with('-'));
+        // options that only make sense for one command, checked once the command is known
+        let mut command_options: Vec<(String, Option<String>)> = vec![];
+        let mut positionals = vec![];
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .ok_or_else(|| format!("Missing value for {}", name))
+            };
+            match arg.as_str() {
+                "--day" => parsed.days.push(parse_day(&value("--day")?)?),
+                "--days" => parsed.days.extend(parse_days(&value("--days")?)?),
+                "--part" => parsed.parts.push(match value("--part")?.as_str() {
+                    "1" => Part::One,
+                    "2" => Part::Two,
+                    part => return Err(format!("Invalid part: {}", part)),
+                }),
+                "--input" => {
+                    parsed.input = Some(match value("--input")?.as_str() {
+                        "-" => InputSource::Stdin,
+                        path => InputSource::File(PathBuf::from(path)),
+                    })
+                }
+                "--format" => parsed.format = value("--format")?.parse()?,
+                "-h" | "--help" => parsed.help = true,
+                "--answers" | "--runs" | "--save" | "--compare" | "--threshold" | "--newlines"
+                | "--digits" => {
+                    let option_value = value(&arg)?;
+                    command_options.push((arg, Some(option_value)))
+                }
+                "--record" | "--stream" | "--highlight" | "--big-int" => {
+                    command_options.push((arg, None))
+                }
+                _ if !arg.starts_with('-') => positionals.push(arg),
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+        let command = command.as_deref().unwrap_or("run");
+        parsed.command = match command {
+            "run" => Command::Run,
+            "verify" => Command::Verify {
+                answers: PathBuf::from(answers::DEFAULT_PATH),
+                record: false,
+            },
+            "bench" => Command::Bench {
+                runs: 10,
+                save: None,
+                compare: None,
+                threshold: 10.0,
+            },
+            "diagnose" => {
+                parsed.days = only_day(&parsed.days, 2, command)?;
+                Command::Diagnose
+            }
+            "trace" => {
+                parsed.days = only_day(&parsed.days, 3, command)?;
+                Command::Trace { highlight: false }
+            }
+            "new-day" => match positionals.as_slice() {
+                [day] => Command::NewDay {
+                    day: parse_day(day)?,
+                },
+                _ => return Err("new-day requires the number of the day".to_string()),
+            },
+            _ => return Err(format!("Unknown command: {}", command)),
+        };
+        if parsed.input.is_some() && parsed.days.len() != 1 {
+            return Err("--input requires exactly one selected day".to_string());
+        }
+        if !positionals.is_empty() && !matches!(parsed.command, Command::NewDay { .. }) {
+            return Err(format!("Unknown argument: {}", positionals[0]));
+        }
+        for (option, value) in command_options {
+            let value = value.unwrap_or_default();
+            match (&mut parsed.command, option.as_str()) {
+                (Command::Verify { answers, .. }, "--answers") => *answers = PathBuf::from(value),
+                (Command::Verify { record, .. }, "--record") => *record = true,
+                (Command::Run, "--stream") => parsed.stream = true,
+                (Command::Trace { highlight }, "--highlight") => *highlight = true,
+                (Command::Run | Command::Verify { .. } | Command::Trace { .. }, "--newlines") => {
+                    parsed.options.newlines = value.parse()?
+                }
+                (Command::Run | Command::Verify { .. } | Command::Trace { .. }, "--digits") => {
+                    parsed.options.digits = value.parse()?
+                }
+                (Command::Run | Command::Verify { .. }, "--big-int") => {
+                    parsed.options.big_int = true
+                }
+                (Command::Bench { runs, .. }, "--runs") => {
+                    *runs = match value.parse() {
+                        Ok(runs @ 1..) => runs,
+                        _ => return Err(format!("Invalid number of runs: {}", value)),
+                    }
+                }
+                (Command::Bench { save, .. }, "--save") => *save = Some(PathBuf::from(value)),
+                (Command::Bench { compare, .. }, "--compare") => {
+                    *compare = Some(PathBuf::from(value))
+                }
+                (Command::Bench { threshold, .. }, "--threshold") => {
+                    *threshold = match value.parse() {
+                        Ok(percent) if percent >= 0.0 => percent,
+                        _ => return Err(format!("Invalid threshold: {}", value)),
+                    }
+                }
+                _ => return Err(format!("{} is not an option of `{}`", option, command)),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Resolve the selected days against the registry, in calendar order.
+    pub fn selected_days(&self) -> Result<Vec<&'static Day>, String> {
+        if self.days.is_empty() {
+            return Ok(calendar::DAYS.iter().collect());
+        }
+        let mut days = self
+            .days
+            .iter()
+            .map(|&number| {
+                calendar::get(number).ok_or_else(|| format!("Day {} is not solved yet", number))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        days.sort_by_key(|day| day.number);
+        days.dedup_by_key(|day| day.number);
+        Ok(days)
+    }
+
+    /// The selected parts in order, each once.
+    pub fn selected_parts(&self) -> Vec<Part> {
+        if self.parts.is_empty() {
+            return Part::BOTH.to_vec();
+        }
+        let mut parts = self.parts.clone();
+        parts.sort();
+        parts.dedup();
+        parts
+    }
+}
+
+/// Days of a command that only exists for one day, which it selects by default.
+fn only_day(days: &[u8], day: u8, command: &str) -> Result<Vec<u8>, String> {
+    if days.iter().all(|&selected| selected == day) {
+        Ok(vec![day])
+    } else {
+        Err(format!("{} is only available for day {}", command, day))
+    }
+}
+
+fn parse_day(day: &str) -> Result<u8, String> {
+    match day.trim().parse() {
+        Ok(number @ 1..=25) => Ok(number),
+        _ => Err(format!("Invalid day: {}", day)),
+    }
+}
+
+fn parse_days(days: &str) -> Result<Vec<u8>, String> {
+    let mut numbers = vec![];
+    for range in days.split(',') {
+        if let Some((first, last)) = range.split_once('-') {
+            let (first, last) = (parse_day(first)?, parse_day(last)?);
+            if first > last {
+                return Err(format!("Invalid range of days: {}", range));
+            }
+            numbers.extend(first..=last);
+        } else {
+            numbers.push(parse_day(range)?);
+        }
+    }
+    Ok(numbers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use advent_of_code_2024::calendar::day03::{Digits, Newlines};
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_day_and_part() {
+        let args = parse(&["--day", "4", "--part", "2"]).unwrap();
+        assert_eq!(args.days, vec![4]);
+        assert_eq!(args.selected_parts(), vec![Part::Two]);
+        assert_eq!(args.format, Format::Calendar);
+        let args = parse(&["--part", "2", "--part", "1", "--part", "2"]).unwrap();
+        assert_eq!(args.selected_parts(), vec![Part::One, Part::Two]);
+    }
+
+    #[test]
+    fn test_parse_days() {
+        let args = parse(&["--days", "1-3,5"]).unwrap();
+        assert_eq!(args.days, vec![1, 2, 3, 5]);
+        assert!(parse(&["--days", "4-2"]).is_err());
+        assert!(parse(&["--days", "0-3"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_input() {
+        let args = parse(&["--day", "1", "--input", "-"]).unwrap();
+        assert_eq!(args.input, Some(InputSource::Stdin));
+        assert!(parse(&["--input", "day01.tsv"]).is_err());
+        assert!(parse(&["--days", "1-2", "--input", "day01.tsv"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse(&["run", "--day", "1"]).unwrap().command, Command::Run);
+        assert_eq!(
+            parse(&["verify", "--record"]).unwrap().command,
+            Command::Verify {
+                answers: PathBuf::from("answers.toml"),
+                record: true,
+            }
+        );
+        assert_eq!(
+            parse(&["bench", "--runs", "5", "--compare", "baseline.csv"])
+                .unwrap()
+                .command,
+            Command::Bench {
+                runs: 5,
+                save: None,
+                compare: Some(PathBuf::from("baseline.csv")),
+                threshold: 10.0,
+            }
+        );
+        assert!(parse(&["--stream"]).unwrap().stream);
+        assert!(parse(&["verify", "--stream"]).is_err());
+        assert!(parse(&["--record"]).is_err());
+        assert!(parse(&["verify", "--runs", "5"]).is_err());
+        assert!(parse(&["bench", "--runs", "0"]).is_err());
+        assert!(parse(&["--day", "1", "verify"]).is_err());
+        assert!(parse(&["solve"]).is_err());
+        assert_eq!(
+            parse(&["new-day", "7"]).unwrap().command,
+            Command::NewDay { day: 7 }
+        );
+        assert!(parse(&["new-day"]).is_err());
+        assert!(parse(&["new-day", "26"]).is_err());
+        assert!(parse(&["run", "7"]).is_err());
+        let args = parse(&["diagnose", "--input", "-"]).unwrap();
+        assert_eq!((args.command, args.days), (Command::Diagnose, vec![2]));
+        assert!(parse(&["diagnose", "--day", "3"]).is_err());
+        assert_eq!(
+            parse(&["trace", "--day", "3", "--highlight"])
+                .unwrap()
+                .command,
+            Command::Trace { highlight: true }
+        );
+        assert!(parse(&["trace", "--days", "2-3"]).is_err());
+        assert_eq!(
+            parse(&["trace", "--newlines", "join"])
+                .unwrap()
+                .options
+                .newlines,
+            Newlines::Join
+        );
+        assert!(parse(&["--newlines", "keep"]).is_err());
+        assert!(parse(&["bench", "--newlines", "join"]).is_err());
+        let options = parse(&["verify", "--digits", "5", "--big-int"])
+            .unwrap()
+            .options;
+        assert_eq!(
+            (options.digits, options.big_int),
+            (Digits::up_to(5).unwrap(), true)
+        );
+        assert!(parse(&["--digits", "20"]).is_err());
+        assert!(parse(&["trace", "--big-int"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid_arguments() {
+        assert!(parse(&["--day"]).is_err());
+        assert!(parse(&["--part", "3"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+        assert!(parse(&["--format", "xml"]).is_err());
+    }
+}
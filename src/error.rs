@@ -0,0 +1,162 @@
+use std::{fmt, str::FromStr};
+
+/// Position in a puzzle input, with 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Location {
+    pub file: Option<String>,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    pub fn new(line: usize, column: usize) -> Location {
+        Location {
+            file: None,
+            line,
+            column,
+        }
+    }
+
+    /// Location of `token`, which has to be a subslice of `line`.
+    pub fn of_token(line_idx: usize, line: &str, token: &str) -> Location {
+        let offset = token.as_ptr() as usize - line.as_ptr() as usize;
+        Location::new(line_idx + 1, line[..offset].chars().count() + 1)
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file)?;
+        }
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    InvalidNumber {
+        token: String,
+        at: Location,
+    },
+    UnexpectedToken {
+        token: String,
+        at: Location,
+    },
+    InvalidChar {
+        found: char,
+        at: Location,
+    },
+    RaggedRow {
+        expected: usize,
+        found: usize,
+        at: Location,
+    },
+    /// Something the input has to contain, such as a value or the guard, is absent
+    Missing {
+        expected: &'static str,
+        at: Location,
+    },
+    /// Evaluating `token` leaves the range of the numbers used for the answer
+    Overflow {
+        token: String,
+        at: Location,
+    },
+    /// Reading a streamed input failed, with the message of the I/O error
+    Io {
+        message: String,
+        at: Location,
+    },
+    /// A structured file such as `answers.toml` is malformed
+    Syntax {
+        message: String,
+        at: Location,
+    },
+}
+
+impl ParseError {
+    pub fn location(&self) -> &Location {
+        match self {
+            ParseError::InvalidNumber { at, .. }
+            | ParseError::UnexpectedToken { at, .. }
+            | ParseError::InvalidChar { at, .. }
+            | ParseError::RaggedRow { at, .. }
+            | ParseError::Missing { at, .. }
+            | ParseError::Overflow { at, .. }
+            | ParseError::Io { at, .. }
+            | ParseError::Syntax { at, .. } => at,
+        }
+    }
+
+    /// Attach the name of the file the input was read from.
+    pub fn in_file(mut self, file: &str) -> ParseError {
+        match &mut self {
+            ParseError::InvalidNumber { at, .. }
+            | ParseError::UnexpectedToken { at, .. }
+            | ParseError::InvalidChar { at, .. }
+            | ParseError::RaggedRow { at, .. }
+            | ParseError::Missing { at, .. }
+            | ParseError::Overflow { at, .. }
+            | ParseError::Io { at, .. }
+            | ParseError::Syntax { at, .. } => at.file = Some(file.to_string()),
+        }
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.location())?;
+        match self {
+            ParseError::InvalidNumber { token, .. } => write!(f, "invalid number `{}`", token),
+            ParseError::UnexpectedToken { token, .. } => write!(f, "unexpected `{}`", token),
+            ParseError::InvalidChar { found, .. } => write!(f, "invalid character `{}`", found),
+            ParseError::RaggedRow {
+                expected, found, ..
+            } => write!(f, "row has {} columns, expected {}", found, expected),
+            ParseError::Missing { expected, .. } => write!(f, "missing {}", expected),
+            ParseError::Overflow { token, .. } => write!(f, "arithmetic overflow in `{}`", token),
+            ParseError::Io { message, .. } => write!(f, "unable to read input: {}", message),
+            ParseError::Syntax { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse `token`, a subslice of line number `line_idx` (0-based), into a number.
+pub fn parse_number<T: FromStr>(line_idx: usize, line: &str, token: &str) -> Result<T, ParseError> {
+    token.parse().map_err(|_| ParseError::InvalidNumber {
+        token: token.to_string(),
+        at: Location::of_token(line_idx, line, token),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_number() {
+        let line = "12   3x";
+        let mut tokens = line.split_whitespace();
+        assert_eq!(parse_number::<u32>(0, line, tokens.next().unwrap()), Ok(12));
+        assert_eq!(
+            parse_number::<u32>(4, line, tokens.next().unwrap()),
+            Err(ParseError::InvalidNumber {
+                token: "3x".to_string(),
+                at: Location::new(5, 6),
+            })
+        );
+    }
+
+    #[test]
+    fn test_display_with_file() {
+        let error = ParseError::Missing {
+            expected: "guard",
+            at: Location::new(1, 1),
+        }
+        .in_file("src/data/day06.txt");
+        assert_eq!(error.to_string(), "src/data/day06.txt:1:1: missing guard");
+    }
+}
@@ -0,0 +1,23 @@
+//! Solutions of [Advent of Code 2024](https://adventofcode.com/2024).
+//!
+//! Every day lives in a module of [`calendar`] and implements [`calendar::Solution`],
+//! so the puzzles can be solved on in-memory inputs:
+//!
+//! ```
+//! use advent_of_code_2024::calendar::{day01::Day01, Solution};
+//!
+//! let lists = Day01::parse("3   4\n4   3\n2   5\n1   3\n3   9\n3   3\n")?;
+//! assert_eq!(Day01::part_one(&lists), 11);
+//! # Ok::<(), advent_of_code_2024::error::ParseError>(())
+//! ```
+//!
+//! The solvers of the single days are public as well, e.g.
+//! [`calendar::day02::check_report_safety`].
+
+pub mod answers;
+pub mod bench;
+pub mod calendar;
+pub mod error;
+pub mod report;
+pub mod scaffold;
+pub mod utils;
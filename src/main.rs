@@ -1,10 +1,362 @@
-mod calendar;
-
-fn main() {
-    calendar::day01();
-    calendar::day02();
-    calendar::day03();
-    calendar::day04();
-    calendar::day05();
-    calendar::day06();
+use advent_of_code_2024::{
+    answers::{self, KnownAnswers},
+    bench,
+    calendar::{
+        day02::{Day02, SafetyPolicy},
+        day03, Answers, Day, Options, Part, Solution,
+    },
+    report::{self, Format, Record, Status},
+    scaffold,
+};
+use cli::{Args, Command, InputSource, USAGE};
+use std::{
+    env,
+    fs::read_to_string,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Duration,
+};
+
+mod cli;
+
+fn main() -> ExitCode {
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    if args.help {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let days = match args.selected_days() {
+        Ok(days) => days,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::from(2);
+        }
+    };
+    match &args.command {
+        Command::Run | Command::Verify { .. } => solve(&args, &days),
+        Command::Bench {
+            runs,
+            save,
+            compare,
+            threshold,
+        } => bench(
+            &args,
+            &days,
+            *runs,
+            save.as_deref(),
+            compare.as_deref(),
+            *threshold,
+        ),
+        Command::Diagnose => diagnose(&args, days[0]),
+        Command::Trace { highlight } => trace(&args, days[0], *highlight),
+        Command::NewDay { day } => new_day(*day),
+    }
+}
+
+fn solve(args: &Args, days: &[&Day]) -> ExitCode {
+    let parts = args.selected_parts();
+    let mut known_answers = match &args.command {
+        Command::Verify { answers, .. } => match KnownAnswers::load(answers) {
+            Ok(known_answers) => Some(known_answers),
+            Err(error) => {
+                eprintln!("{}", error);
+                return ExitCode::from(2);
+            }
+        },
+        _ => None,
+    };
+
+    let mut stdout = io::stdout().lock();
+    let mut records = vec![];
+    let mut new_answers = 0;
+    for day in days {
+        let (hash, mut day_records) =
+            run_day(day, &parts, args.input.as_ref(), args.stream, &args.options);
+        if let (Some(known_answers), Some(hash)) = (&mut known_answers, hash) {
+            for record in &mut day_records {
+                verify(record, known_answers, &hash);
+                if let (Status::New, Some(answer)) = (&record.status, &record.answer) {
+                    known_answers.insert(record.day, record.part, &hash, answer);
+                    new_answers += 1;
+                }
+            }
+        }
+        if let Some(message) = day_records.iter().find_map(|record| match &record.status {
+            Status::Error(message) => Some(message),
+            _ => None,
+        }) {
+            eprintln!("{}", message);
+        }
+        if args.format == Format::Calendar {
+            report::write_calendar_line(&mut stdout, day, &day_records)
+                .expect("Unable to write output");
+        }
+        records.extend(day_records);
+    }
+    report::write_records(&mut stdout, args.format, &records).expect("Unable to write output");
+
+    if let (
+        Command::Verify {
+            answers,
+            record: true,
+        },
+        Some(known_answers),
+    ) = (&args.command, &known_answers)
+    {
+        if new_answers > 0 {
+            if let Err(error) = known_answers.save(answers) {
+                eprintln!("{}: {}", answers.display(), error);
+                return ExitCode::FAILURE;
+            }
+            eprintln!(
+                "Recorded {} new answers in {}",
+                new_answers,
+                answers.display()
+            );
+        }
+    }
+    if records.iter().any(|record| record.status.is_failure()) {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn bench(
+    args: &Args,
+    days: &[&Day],
+    runs: usize,
+    save: Option<&Path>,
+    compare: Option<&Path>,
+    threshold: f64,
+) -> ExitCode {
+    let baseline = match compare.map(bench::load_baseline).transpose() {
+        Ok(baseline) => baseline,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::from(2);
+        }
+    };
+    let parts = args.selected_parts();
+    let mut failed = false;
+    let mut measurements = vec![];
+    for day in days {
+        let (file, input) = read_input(day, args.input.as_ref());
+        let timings = input
+            .map_err(|error| format!("{}: {}", file, error))
+            .and_then(|input| {
+                day.bench(&input, runs)
+                    .map_err(|error| error.in_file(&file).to_string())
+            });
+        match timings {
+            Ok(timings) => measurements.extend(bench::measure(day.number, &timings, &parts)),
+            Err(error) => {
+                eprintln!("{}", error);
+                failed = true;
+            }
+        }
+    }
+    let regressions = bench::write_table(
+        &mut io::stdout().lock(),
+        &measurements,
+        baseline.as_ref(),
+        threshold,
+    )
+    .expect("Unable to write output");
+    if let Some(path) = save {
+        if let Err(error) = bench::save_baseline(path, &measurements) {
+            eprintln!("{}", error);
+            failed = true;
+        }
+    }
+    if regressions > 0 {
+        eprintln!(
+            "{} phases regressed by more than {}%",
+            regressions, threshold
+        );
+    }
+    if failed || regressions > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Print every unsafe report of day 2 annotated with its diagnosis.
+fn diagnose(args: &Args, day: &Day) -> ExitCode {
+    let (file, input) = read_input(day, args.input.as_ref());
+    let reports = input
+        .map_err(|error| format!("{}: {}", file, error))
+        .and_then(|input| Day02::parse(&input).map_err(|error| error.in_file(&file).to_string()));
+    let reports = match reports {
+        Ok(reports) => reports,
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+    let (mut unsafe_reports, mut fixable) = (0, 0);
+    for (line_idx, report) in reports.iter().enumerate() {
+        if let Some(diagnosis) = SafetyPolicy::STRICT.diagnose(report) {
+            println!("{:>4}: {}", line_idx + 1, diagnosis.annotate(report));
+            unsafe_reports += 1;
+            fixable += usize::from(diagnosis.fix.is_some());
+        }
+    }
+    println!(
+        "{} of {} reports are unsafe, {} of them fixable by removing one level",
+        unsafe_reports,
+        reports.len(),
+        fixable
+    );
+    ExitCode::SUCCESS
+}
+
+/// Print the instructions of day 3 with their effect, or the memory highlighted.
+fn trace(args: &Args, day: &Day, highlight: bool) -> ExitCode {
+    let (file, input) = read_input(day, args.input.as_ref());
+    let memory = match input {
+        Ok(input) => day03::memory(&input, args.options.newlines).into_owned(),
+        Err(error) => {
+            eprintln!("{}: {}", file, error);
+            return ExitCode::FAILURE;
+        }
+    };
+    let instructions = args.options.digits.instructions();
+    let tokens: Vec<_> = match day03::lex(&memory, &instructions) {
+        Ok(lexer) => lexer.collect(),
+        Err(error) => {
+            eprintln!("{}", error);
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut stdout = io::stdout().lock();
+    if highlight {
+        writeln!(stdout, "{}", day03::highlight(&memory, &tokens)).expect("Unable to write output");
+        return ExitCode::SUCCESS;
+    }
+    let (steps, overflow) = day03::trace(&tokens);
+    day03::write_trace(&mut stdout, &memory, &steps).expect("Unable to write output");
+    match overflow {
+        Some(overflow) => {
+            eprintln!("{}: {}", file, overflow);
+            ExitCode::FAILURE
+        }
+        None => ExitCode::SUCCESS,
+    }
+}
+
+fn new_day(day: u8) -> ExitCode {
+    match scaffold::new_day(Path::new("."), day) {
+        Ok(changed) => {
+            for path in changed {
+                println!("{}", path.display());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn read_input(day: &Day, input: Option<&InputSource>) -> (String, io::Result<String>) {
+    match input {
+        Some(source) => (source.to_string(), source.read()),
+        None => (day.input.to_string(), read_to_string(day.input)),
+    }
+}
+
+/// Answer the selected parts of `day` from its input as a whole, or line by
+/// line if `stream` is set and the day supports it, turning a failure to read
+/// or parse the input into an error record for each part. Also returns the
+/// hash of the input, if it was read as a whole.
+fn run_day(
+    day: &Day,
+    parts: &[Part],
+    input: Option<&InputSource>,
+    stream: bool,
+    options: &Options,
+) -> (Option<String>, Vec<Record>) {
+    let (hash, answers) = match stream
+        .then(|| stream_day(day, parts, input, options))
+        .flatten()
+    {
+        Some(answers) => (None, answers),
+        None => {
+            let (file, input) = read_input(day, input);
+            let hash = input.as_deref().ok().map(answers::input_hash);
+            let answers = input
+                .map_err(|error| format!("{}: {}", file, error))
+                .and_then(|input| {
+                    day.solve(&input, parts, options)
+                        .map_err(|error| error.in_file(&file).to_string())
+                });
+            (hash, answers)
+        }
+    };
+    let records = match answers {
+        Ok(answers) => answers
+            .into_iter()
+            .map(|answer| Record {
+                day: day.number,
+                part: answer.part,
+                answer: Some(answer.value),
+                duration: answer.duration,
+                status: Status::Ok,
+            })
+            .collect(),
+        Err(message) => parts
+            .iter()
+            .map(|&part| Record {
+                day: day.number,
+                part,
+                answer: None,
+                duration: Duration::ZERO,
+                status: Status::Error(message.clone()),
+            })
+            .collect(),
+    };
+    (hash, records)
+}
+
+/// Answer from the input read line by line, or `None` if the day needs its input as a whole.
+fn stream_day(
+    day: &Day,
+    parts: &[Part],
+    input: Option<&InputSource>,
+    options: &Options,
+) -> Option<Result<Answers, String>> {
+    if !day.can_stream() {
+        return None;
+    }
+    let source = input
+        .cloned()
+        .unwrap_or_else(|| InputSource::File(PathBuf::from(day.input)));
+    let mut reader = match source.reader() {
+        Ok(reader) => reader,
+        Err(error) => return Some(Err(format!("{}: {}", source, error))),
+    };
+    day.solve_stream(&mut reader, parts, options)
+        .map(|answers| answers.map_err(|error| error.in_file(&source.to_string()).to_string()))
+}
+
+fn verify(record: &mut Record, known_answers: &KnownAnswers, hash: &str) {
+    if let (Status::Ok, Some(answer)) = (&record.status, &record.answer) {
+        record.status = match known_answers.get(record.day, record.part, hash) {
+            Some(expected) if expected == answer => Status::Pass,
+            Some(expected) => Status::Fail {
+                expected: expected.to_string(),
+            },
+            None => Status::New,
+        };
+    }
 }
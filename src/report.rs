@@ -0,0 +1,293 @@
+use crate::calendar::{Day, Part};
+use std::{
+    io::{self, Write},
+    str::FromStr,
+    time::Duration,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// Answers next to the ASCII-art calendar, one line per day
+    #[default]
+    Calendar,
+    Json,
+    Csv,
+    Junit,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Format, String> {
+        match format {
+            "calendar" => Ok(Format::Calendar),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "junit" => Ok(Format::Junit),
+            _ => Err(format!("Invalid format: {}", format)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// Answered, without comparing against the known answers
+    Ok,
+    /// Answer matches the known answer
+    Pass,
+    /// Answer differs from the known answer
+    Fail {
+        expected: String,
+    },
+    /// No known answer for this input yet
+    New,
+    Error(String),
+}
+
+impl Status {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Pass => "pass",
+            Status::Fail { .. } => "fail",
+            Status::New => "new",
+            Status::Error(_) => "error",
+        }
+    }
+
+    pub fn message(&self) -> Option<String> {
+        match self {
+            Status::Ok | Status::Pass | Status::New => None,
+            Status::Fail { expected } => Some(format!("expected {}", expected)),
+            Status::Error(message) => Some(message.clone()),
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Status::Fail { .. } | Status::Error(_))
+    }
+}
+
+/// Outcome of one part of one day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub day: u8,
+    pub part: Part,
+    pub answer: Option<String>,
+    /// Time spent answering the part, excluding reading and parsing the input
+    pub duration: Duration,
+    pub status: Status,
+}
+
+/// Print the calendar line of `day`, as soon as its records are known.
+pub fn write_calendar_line(out: &mut impl Write, day: &Day, records: &[Record]) -> io::Result<()> {
+    write!(out, "{}{:>2}: ", day.art, day.number)?;
+    let answers = records
+        .iter()
+        .map(|record| {
+            let label = day.label(record.part);
+            match (&record.answer, &record.status) {
+                (Some(answer), Status::Ok) => format!("{}: {}", label, answer),
+                (Some(answer), status) => match status.message() {
+                    Some(message) => {
+                        format!("{}: {} ({}, {})", label, answer, status.name(), message)
+                    }
+                    None => format!("{}: {} ({})", label, answer, status.name()),
+                },
+                (None, status) => format!("{}: {}", label, status.name()),
+            }
+        })
+        .collect::<Vec<_>>();
+    writeln!(out, "{}", answers.join(", "))
+}
+
+/// Print all records in one of the machine-readable formats.
+pub fn write_records(out: &mut impl Write, format: Format, records: &[Record]) -> io::Result<()> {
+    match format {
+        Format::Calendar => Ok(()),
+        Format::Json => write_json(out, records),
+        Format::Csv => write_csv(out, records),
+        Format::Junit => write_junit(out, records),
+    }
+}
+
+fn write_json(out: &mut impl Write, records: &[Record]) -> io::Result<()> {
+    writeln!(out, "[")?;
+    for (idx, record) in records.iter().enumerate() {
+        let answer = record
+            .answer
+            .as_deref()
+            .map_or("null".to_string(), json_string);
+        let message = record
+            .status
+            .message()
+            .map_or("null".to_string(), |message| json_string(&message));
+        write!(
+            out,
+            r#"  {{"day": {}, "part": {}, "answer": {}, "duration_ns": {}, "status": "{}", "message": {}}}"#,
+            record.day,
+            record.part,
+            answer,
+            record.duration.as_nanos(),
+            record.status.name(),
+            message
+        )?;
+        writeln!(out, "{}", if idx + 1 < records.len() { "," } else { "" })?;
+    }
+    writeln!(out, "]")
+}
+
+fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str(r#"\""#),
+            '\\' => escaped.push_str(r"\\"),
+            '\n' => escaped.push_str(r"\n"),
+            '\r' => escaped.push_str(r"\r"),
+            '\t' => escaped.push_str(r"\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn write_csv(out: &mut impl Write, records: &[Record]) -> io::Result<()> {
+    let mut writer = csv::Writer::from_writer(out);
+    writer.write_record(["day", "part", "answer", "duration_ns", "status", "message"])?;
+    for record in records {
+        writer.write_record([
+            record.day.to_string(),
+            record.part.to_string(),
+            record.answer.clone().unwrap_or_default(),
+            record.duration.as_nanos().to_string(),
+            record.status.name().to_string(),
+            record.status.message().unwrap_or_default(),
+        ])?;
+    }
+    writer.flush()
+}
+
+fn write_junit(out: &mut impl Write, records: &[Record]) -> io::Result<()> {
+    let failures = records
+        .iter()
+        .filter(|record| matches!(record.status, Status::Fail { .. }))
+        .count();
+    let errors = records
+        .iter()
+        .filter(|record| matches!(record.status, Status::Error(_)))
+        .count();
+    let time: Duration = records.iter().map(|record| record.duration).sum();
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<testsuite name="advent_of_code_2024" tests="{}" failures="{}" errors="{}" time="{:.6}">"#,
+        records.len(),
+        failures,
+        errors,
+        time.as_secs_f64()
+    )?;
+    for record in records {
+        write!(
+            out,
+            r#"  <testcase classname="day{:02}" name="part{}" time="{:.6}""#,
+            record.day,
+            record.part,
+            record.duration.as_secs_f64()
+        )?;
+        match &record.status {
+            Status::Ok | Status::Pass | Status::New => writeln!(
+                out,
+                "><system-out>{}</system-out></testcase>",
+                xml_escape(record.answer.as_deref().unwrap_or_default())
+            )?,
+            Status::Fail { expected } => writeln!(
+                out,
+                r#"><failure message="expected {}, got {}"/></testcase>"#,
+                xml_escape(expected),
+                xml_escape(record.answer.as_deref().unwrap_or_default())
+            )?,
+            Status::Error(message) => writeln!(
+                out,
+                r#"><error message="{}"/></testcase>"#,
+                xml_escape(message)
+            )?,
+        }
+    }
+    writeln!(out, "</testsuite>")
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_records() -> Vec<Record> {
+        vec![
+            Record {
+                day: 1,
+                part: Part::One,
+                answer: Some("11".to_string()),
+                duration: Duration::from_micros(1500),
+                status: Status::Ok,
+            },
+            Record {
+                day: 2,
+                part: Part::Two,
+                answer: None,
+                duration: Duration::ZERO,
+                status: Status::Error("day02.tsv:1:3: invalid number `\"x\"`".to_string()),
+            },
+        ]
+    }
+
+    fn render(format: Format) -> String {
+        let mut out = vec![];
+        write_records(&mut out, format, &get_records()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_write_json() {
+        assert_eq!(
+            render(Format::Json),
+            r#"[
+  {"day": 1, "part": 1, "answer": "11", "duration_ns": 1500000, "status": "ok", "message": null},
+  {"day": 2, "part": 2, "answer": null, "duration_ns": 0, "status": "error", "message": "day02.tsv:1:3: invalid number `\"x\"`"}
+]
+"#
+        );
+    }
+
+    #[test]
+    fn test_write_csv() {
+        assert_eq!(
+            render(Format::Csv),
+            r#"day,part,answer,duration_ns,status,message
+1,1,11,1500000,ok,
+2,2,,0,error,"day02.tsv:1:3: invalid number `""x""`"
+"#
+        );
+    }
+
+    #[test]
+    fn test_write_junit() {
+        let junit = render(Format::Junit);
+        assert!(junit.contains(r#"tests="2" failures="0" errors="1""#));
+        assert!(junit.contains(
+            r#"<testcase classname="day01" name="part1" time="0.001500"><system-out>11</system-out>"#
+        ));
+        assert!(junit.contains(r#"message="day02.tsv:1:3: invalid number `&quot;x&quot;`""#));
+    }
+}
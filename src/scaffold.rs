@@ -0,0 +1,170 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Calendar line for a day whose ASCII art has not been drawn yet, as wide as the others
+const PLACEHOLDER_ART: &str = "|               |        |                      | ";
+
+const TEMPLATE: &str = r#####"use super::Solution;
+use crate::error::ParseError;
+
+pub struct Day{{NN}};
+
+impl Solution for Day{{NN}} {
+    const DAY: u8 = {{N}};
+    const ART: &'static str = r"{{ART}}";
+    const INPUT: &'static str = "src/data/day{{NN}}.txt";
+    const LABELS: [&'static str; 2] = ["Part one", "part two"];
+
+    type Input = Vec<String>;
+    type PartOne = u64;
+    type PartTwo = u64;
+
+    fn parse(input: &str) -> Result<Self::Input, ParseError> {
+        Ok(input.lines().map(str::to_string).collect())
+    }
+
+    fn part_one(_input: &Self::Input) -> u64 {
+        0
+    }
+
+    fn part_two(_input: &Self::Input) -> u64 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../data/day{{NN}}.example.txt");
+
+    #[test]
+    fn test_parse_example() {
+        assert!(Day{{NN}}::parse(EXAMPLE).is_ok());
+    }
+}
+"#####;
+
+/// Render the module of a new day from the template.
+pub fn render_module(day: u8) -> String {
+    TEMPLATE
+        .replace("{{NN}}", &format!("{:02}", day))
+        .replace("{{N}}", &day.to_string())
+        .replace("{{ART}}", PLACEHOLDER_ART)
+}
+
+/// Add the `mod` declaration and the registry entry of a new day to the
+/// content of `calendar/mod.rs`, keeping both in calendar order.
+pub fn wire_module(mod_rs: &str, day: u8) -> Result<String, String> {
+    let module = format!("day{:02}", day);
+    let declaration = format!("pub mod {};", module);
+    let entry = format!("    Day::new::<{}::Day{:02}>(),", module, day);
+    if mod_rs.lines().any(|line| line == declaration) {
+        return Err(format!("{} is already declared in calendar/mod.rs", module));
+    }
+    let mut lines: Vec<String> = mod_rs.lines().map(str::to_string).collect();
+    let insert_after = |lines: &Vec<String>, prefix: &str, new_line: &str| {
+        lines
+            .iter()
+            .enumerate()
+            .rfind(|(_, line)| line.starts_with(prefix) && line.as_str() < new_line)
+            .map(|(idx, _)| idx + 1)
+            .or_else(|| lines.iter().position(|line| line.starts_with(prefix)))
+            .ok_or_else(|| format!("No `{}` line found in calendar/mod.rs", prefix.trim()))
+    };
+    let idx = insert_after(&lines, "pub mod day", &declaration)?;
+    lines.insert(idx, declaration);
+    let idx = insert_after(&lines, "    Day::new::<day", &entry)?;
+    lines.insert(idx, entry);
+    Ok(lines.join("\n") + "\n")
+}
+
+/// Create the module, input placeholders and registry entry of a new day
+/// below `root`, and return the created or changed files.
+pub fn new_day(root: &Path, day: u8) -> Result<Vec<PathBuf>, String> {
+    let module_path = root.join(format!("src/calendar/day{:02}.rs", day));
+    let mod_rs_path = root.join("src/calendar/mod.rs");
+    let data_paths = [
+        root.join(format!("src/data/day{:02}.txt", day)),
+        root.join(format!("src/data/day{:02}.example.txt", day)),
+    ];
+    if module_path.exists() {
+        return Err(format!("{} already exists", module_path.display()));
+    }
+    let error = |path: &Path, error: std::io::Error| format!("{}: {}", path.display(), error);
+    let mod_rs = fs::read_to_string(&mod_rs_path).map_err(|e| error(&mod_rs_path, e))?;
+    let mod_rs = wire_module(&mod_rs, day)?;
+
+    let mut changed = vec![];
+    fs::write(&module_path, render_module(day)).map_err(|e| error(&module_path, e))?;
+    changed.push(module_path);
+    for data_path in data_paths {
+        if !data_path.exists() {
+            fs::write(&data_path, "").map_err(|e| error(&data_path, e))?;
+            changed.push(data_path);
+        }
+    }
+    fs::write(&mod_rs_path, mod_rs).map_err(|e| error(&mod_rs_path, e))?;
+    changed.push(mod_rs_path);
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOD_RS: &str = "use std::fmt::Display;
+
+pub mod day01;
+pub mod day02;
+pub mod day10;
+
+pub const DAYS: &[Day] = &[
+    Day::new::<day01::Day01>(),
+    Day::new::<day02::Day02>(),
+    Day::new::<day10::Day10>(),
+];
+";
+
+    #[test]
+    fn test_wire_module() {
+        assert_eq!(
+            wire_module(MOD_RS, 7).unwrap(),
+            "use std::fmt::Display;
+
+pub mod day01;
+pub mod day02;
+pub mod day07;
+pub mod day10;
+
+pub const DAYS: &[Day] = &[
+    Day::new::<day01::Day01>(),
+    Day::new::<day02::Day02>(),
+    Day::new::<day07::Day07>(),
+    Day::new::<day10::Day10>(),
+];
+"
+        );
+        assert!(wire_module(MOD_RS, 2).is_err());
+        assert!(wire_module("", 7).is_err());
+    }
+
+    #[test]
+    fn test_render_module() {
+        let module = render_module(7);
+        assert!(module.contains("pub struct Day07;"));
+        assert!(module.contains("const DAY: u8 = 7;"));
+        assert!(module.contains(r#"include_str!("../data/day07.example.txt")"#));
+        assert!(!module.contains("{{"));
+    }
+
+    #[test]
+    fn test_placeholder_art_is_as_wide_as_the_calendar() {
+        use crate::calendar::DAYS;
+        for day in DAYS {
+            assert_eq!(PLACEHOLDER_ART.len(), day.art.chars().count());
+        }
+    }
+}
@@ -0,0 +1,6 @@
+//! ANSI escape codes for colouring terminal output
+
+pub const GREEN: &str = "\x1b[32m";
+pub const BOLD_GREEN: &str = "\x1b[1;32m";
+pub const DIM: &str = "\x1b[2m";
+pub const RESET: &str = "\x1b[0m";
@@ -0,0 +1,191 @@
+use super::Point;
+
+/// One of the four orthogonal directions on a grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Dir4 {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Dir4 {
+    /// All directions, clockwise starting upwards
+    pub const ALL: [Dir4; 4] = [Dir4::Up, Dir4::Right, Dir4::Down, Dir4::Left];
+
+    pub fn iter() -> impl Iterator<Item = Dir4> {
+        Dir4::ALL.into_iter()
+    }
+
+    /// Offset of one step in this direction
+    pub fn delta(self) -> Point {
+        Dir8::from(self).delta()
+    }
+
+    pub fn turn_right(self) -> Dir4 {
+        Dir4::ALL[(self as usize + 1) % 4]
+    }
+
+    pub fn turn_left(self) -> Dir4 {
+        Dir4::ALL[(self as usize + 3) % 4]
+    }
+
+    pub fn reverse(self) -> Dir4 {
+        Dir4::ALL[(self as usize + 2) % 4]
+    }
+
+    /// Arrow as drawn in the puzzles, e.g. `^` for [`Dir4::Up`]
+    pub fn arrow(self) -> char {
+        match self {
+            Dir4::Up => '^',
+            Dir4::Right => '>',
+            Dir4::Down => 'v',
+            Dir4::Left => '<',
+        }
+    }
+}
+
+impl From<Dir4> for char {
+    fn from(direction: Dir4) -> char {
+        direction.arrow()
+    }
+}
+
+impl TryFrom<char> for Dir4 {
+    type Error = char;
+
+    fn try_from(c: char) -> Result<Dir4, char> {
+        Dir4::iter()
+            .find(|direction| direction.arrow() == c)
+            .ok_or(c)
+    }
+}
+
+/// One of the eight orthogonal or diagonal directions on a grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Dir8 {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl Dir8 {
+    /// All directions, clockwise starting upwards
+    pub const ALL: [Dir8; 8] = [
+        Dir8::Up,
+        Dir8::UpRight,
+        Dir8::Right,
+        Dir8::DownRight,
+        Dir8::Down,
+        Dir8::DownLeft,
+        Dir8::Left,
+        Dir8::UpLeft,
+    ];
+
+    pub fn iter() -> impl Iterator<Item = Dir8> {
+        Dir8::ALL.into_iter()
+    }
+
+    /// Offset of one step in this direction
+    pub fn delta(self) -> Point {
+        match self {
+            Dir8::Up => Point::new(0, -1),
+            Dir8::UpRight => Point::new(1, -1),
+            Dir8::Right => Point::new(1, 0),
+            Dir8::DownRight => Point::new(1, 1),
+            Dir8::Down => Point::new(0, 1),
+            Dir8::DownLeft => Point::new(-1, 1),
+            Dir8::Left => Point::new(-1, 0),
+            Dir8::UpLeft => Point::new(-1, -1),
+        }
+    }
+
+    /// Turn right by 45 degrees
+    pub fn turn_right(self) -> Dir8 {
+        Dir8::ALL[(self as usize + 1) % 8]
+    }
+
+    /// Turn left by 45 degrees
+    pub fn turn_left(self) -> Dir8 {
+        Dir8::ALL[(self as usize + 7) % 8]
+    }
+
+    pub fn reverse(self) -> Dir8 {
+        Dir8::ALL[(self as usize + 4) % 8]
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        self as usize % 2 == 1
+    }
+
+    pub fn arrow(self) -> char {
+        match self {
+            Dir8::Up => '↑',
+            Dir8::UpRight => '↗',
+            Dir8::Right => '→',
+            Dir8::DownRight => '↘',
+            Dir8::Down => '↓',
+            Dir8::DownLeft => '↙',
+            Dir8::Left => '←',
+            Dir8::UpLeft => '↖',
+        }
+    }
+}
+
+impl From<Dir4> for Dir8 {
+    fn from(direction: Dir4) -> Dir8 {
+        Dir8::ALL[direction as usize * 2]
+    }
+}
+
+impl From<Dir8> for char {
+    fn from(direction: Dir8) -> char {
+        direction.arrow()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dir4_rotation() {
+        assert_eq!(Dir4::Up.turn_right(), Dir4::Right);
+        assert_eq!(Dir4::Up.turn_left(), Dir4::Left);
+        assert_eq!(Dir4::Left.reverse(), Dir4::Right);
+        for direction in Dir4::iter() {
+            assert_eq!(direction.turn_right().turn_left(), direction);
+            assert_eq!(direction.reverse().delta(), -direction.delta());
+            assert_eq!(Dir4::try_from(direction.arrow()), Ok(direction));
+        }
+        assert_eq!(Dir4::try_from('x'), Err('x'));
+    }
+
+    #[test]
+    fn test_dir8_rotation() {
+        assert_eq!(Dir8::UpLeft.turn_right(), Dir8::Up);
+        assert_eq!(Dir8::Up.turn_left(), Dir8::UpLeft);
+        for direction in Dir8::iter() {
+            assert_eq!((0..8).fold(direction, |d, _| d.turn_left()), direction);
+            assert_eq!(direction.turn_right().turn_left(), direction);
+            assert_eq!(direction.reverse().delta(), -direction.delta());
+        }
+        assert_eq!(Dir8::iter().filter(|d| d.is_diagonal()).count(), 4);
+    }
+
+    #[test]
+    fn test_deltas_share_axis_order() {
+        assert_eq!(Dir4::Right.delta(), Point::new(1, 0));
+        assert_eq!(Dir4::Down.delta(), Point::new(0, 1));
+        assert_eq!(Dir8::from(Dir4::Left), Dir8::Left);
+        assert_eq!(
+            Dir8::DownLeft.delta(),
+            Dir4::Down.delta() + Dir4::Left.delta()
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use super::{Dir4, Dir8, Point};
+use crate::error::{Location, ParseError};
+use std::fmt;
+
+/// Rectangular grid stored row by row in one flat vector.
+///
+/// Cells are addressed by signed [`Point`]s, `x` being the column and `y`
+/// the row counted downwards, so that stepping off the grid yields `None`
+/// instead of wrapping around or panicking.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Build a grid from its rows, rejecting empty grids and ragged rows.
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Grid<T>, ParseError> {
+        let width = rows
+            .first()
+            .ok_or(ParseError::Missing {
+                expected: "rows",
+                at: Location::new(1, 1),
+            })?
+            .len();
+        if width == 0 {
+            return Err(ParseError::Missing {
+                expected: "columns",
+                at: Location::new(1, 1),
+            });
+        }
+        let height = rows.len();
+        let mut cells = Vec::with_capacity(width * height);
+        for (row_idx, row) in rows.into_iter().enumerate() {
+            if row.len() != width {
+                return Err(ParseError::RaggedRow {
+                    expected: width,
+                    found: row.len(),
+                    at: Location::new(row_idx + 1, row.len().min(width) + 1),
+                });
+            }
+            cells.extend(row);
+        }
+        Ok(Grid {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        point.x >= 0
+            && point.y >= 0
+            && (point.x as usize) < self.width
+            && (point.y as usize) < self.height
+    }
+
+    /// The cell `point` lands on when the grid is seen as a torus, its
+    /// edges wrapping around to the opposite ones. The grid must not be empty.
+    pub fn wrap(&self, point: Point) -> Point {
+        Point::new(
+            point.x.rem_euclid(self.width as isize),
+            point.y.rem_euclid(self.height as isize),
+        )
+    }
+
+    fn index(&self, point: Point) -> Option<usize> {
+        self.contains(point)
+            .then(|| point.y as usize * self.width + point.x as usize)
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.index(point).map(|idx| &self.cells[idx])
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        self.index(point).map(|idx| &mut self.cells[idx])
+    }
+
+    /// All cells, row by row
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// All cells with their coordinates, row by row
+    pub fn positions(&self) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.rows().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .map(move |(x, cell)| (Point::new(x as isize, y as isize), cell))
+        })
+    }
+
+    pub fn neighbours4(&self, point: Point) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.neighbours(point, Dir4::iter().map(Dir4::delta))
+    }
+
+    pub fn neighbours8(&self, point: Point) -> impl Iterator<Item = (Point, &T)> + '_ {
+        self.neighbours(point, Dir8::iter().map(Dir8::delta))
+    }
+
+    fn neighbours<'a>(
+        &'a self,
+        point: Point,
+        offsets: impl Iterator<Item = Point> + 'a,
+    ) -> impl Iterator<Item = (Point, &'a T)> + 'a {
+        offsets.filter_map(move |offset| {
+            let neighbour = point + offset;
+            self.get(neighbour).map(|cell| (neighbour, cell))
+        })
+    }
+
+    /// Cells from `start` on, moving by `step` until leaving the grid
+    pub fn line(&self, start: Point, step: Point) -> impl Iterator<Item = (Point, &T)> + '_ {
+        (0..)
+            .map(move |n| start + step * n)
+            .map_while(|point| self.get(point).map(|cell| (point, cell)))
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> + '_ {
+        (0..self.height).map(|y| self.row(y))
+    }
+
+    /// Columns from left to right, each read downwards
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = (Point, &T)> + '_> + '_ {
+        (0..self.width as isize).map(|x| self.line(Point::new(x, 0), Dir4::Down.delta()))
+    }
+
+    /// Diagonals read down and to the right, starting at the top or left edge
+    pub fn diagonals(&self) -> impl Iterator<Item = impl Iterator<Item = (Point, &T)> + '_> + '_ {
+        let top = (0..self.width as isize).map(|x| Point::new(x, 0));
+        let left = (1..self.height as isize).map(|y| Point::new(0, y));
+        top.chain(left)
+            .map(|start| self.line(start, Dir8::DownRight.delta()))
+    }
+
+    /// Diagonals read down and to the left, starting at the top or right edge
+    pub fn anti_diagonals(
+        &self,
+    ) -> impl Iterator<Item = impl Iterator<Item = (Point, &T)> + '_> + '_ {
+        let right_x = self.width as isize - 1;
+        let top = (0..self.width as isize).map(|x| Point::new(x, 0));
+        let right = (1..self.height as isize).map(move |y| Point::new(right_x, y));
+        top.chain(right)
+            .map(|start| self.line(start, Dir8::DownLeft.delta()))
+    }
+
+    /// The grid turned by 90 degrees clockwise
+    pub fn rotated(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let cells = (0..self.width)
+            .flat_map(|x| (0..self.height).rev().map(move |y| (x, y)))
+            .map(|(x, y)| self.cells[y * self.width + x].clone())
+            .collect();
+        Grid {
+            width: self.height,
+            height: self.width,
+            cells,
+        }
+    }
+
+    /// The grid mirrored from left to right
+    pub fn mirrored(&self) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self
+                .rows()
+                .flat_map(|row| row.iter().rev().cloned())
+                .collect(),
+        }
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl Grid<char> {
+    /// Parse a rectangular grid of characters, one row per line.
+    pub fn parse(input: &str) -> Result<Grid<char>, ParseError> {
+        Grid::from_rows(input.lines().map(|line| line.chars().collect()).collect())
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_grid() -> Grid<char> {
+        Grid::parse("abc\ndef\n").unwrap()
+    }
+
+    fn collect<'a>(line: impl Iterator<Item = (Point, &'a char)>) -> String {
+        line.map(|(_, &c)| c).collect()
+    }
+
+    #[test]
+    fn test_empty_grids() {
+        assert_eq!(
+            Grid::parse("\n"),
+            Err(ParseError::Missing {
+                expected: "columns",
+                at: Location::new(1, 1),
+            })
+        );
+        assert!(Grid::parse("").is_err());
+        let grid = Grid::new(0, 3, 'a');
+        assert_eq!(grid.rows().collect::<Vec<_>>(), vec![&[] as &[char]; 3]);
+        assert_eq!(grid.positions().count(), 0);
+        assert_eq!(grid.to_string(), "\n\n\n");
+    }
+
+    #[test]
+    fn test_parse() {
+        let grid = get_grid();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.get(Point::new(2, 1)), Some(&'f'));
+        assert_eq!(grid.get(Point::new(3, 0)), None);
+        assert_eq!(grid.get(Point::new(0, -1)), None);
+        assert_eq!(grid.to_string(), "abc\ndef\n");
+        assert_eq!(
+            Grid::parse("ab\nc\n"),
+            Err(ParseError::RaggedRow {
+                expected: 2,
+                found: 1,
+                at: Location::new(2, 2),
+            })
+        );
+        assert!(Grid::parse("").is_err());
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = get_grid();
+        let neighbours4: Vec<_> = grid.neighbours4(Point::ORIGIN).collect();
+        assert_eq!(
+            neighbours4,
+            vec![(Point::new(1, 0), &'b'), (Point::new(0, 1), &'d')]
+        );
+        assert_eq!(collect(grid.neighbours8(Point::new(1, 0))), "cfeda");
+    }
+
+    #[test]
+    fn test_lines() {
+        let grid = get_grid();
+        assert_eq!(
+            grid.rows().collect::<Vec<_>>(),
+            vec![['a', 'b', 'c'], ['d', 'e', 'f']]
+        );
+        assert_eq!(
+            grid.columns().map(collect).collect::<Vec<_>>(),
+            ["ad", "be", "cf"]
+        );
+        assert_eq!(
+            grid.diagonals().map(collect).collect::<Vec<_>>(),
+            ["ae", "bf", "c", "d"]
+        );
+        assert_eq!(
+            grid.anti_diagonals().map(collect).collect::<Vec<_>>(),
+            ["a", "bd", "ce", "f"]
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        let grid = get_grid();
+        assert_eq!(grid.wrap(Point::new(1, 1)), Point::new(1, 1));
+        assert_eq!(grid.wrap(Point::new(-1, 2)), Point::new(2, 0));
+        assert_eq!(grid.wrap(Point::new(7, -3)), Point::new(1, 1));
+    }
+
+    #[test]
+    fn test_rotated_and_mirrored() {
+        let grid = get_grid();
+        assert_eq!(grid.rotated().to_string(), "da\neb\nfc\n");
+        assert_eq!(grid.rotated().rotated(), Grid::parse("fed\ncba\n").unwrap());
+        assert_eq!(grid.mirrored().to_string(), "cba\nfed\n");
+    }
+
+    #[test]
+    fn test_get_mut_and_map() {
+        let mut grid = get_grid();
+        *grid.get_mut(Point::new(1, 1)).unwrap() = 'x';
+        assert_eq!(
+            grid.map(|&c| c == 'x')
+                .cells()
+                .iter()
+                .filter(|&&x| x)
+                .count(),
+            1
+        );
+        assert_eq!(grid.get_mut(Point::new(-1, 0)), None);
+    }
+}
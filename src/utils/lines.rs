@@ -0,0 +1,61 @@
+use crate::error::{Location, ParseError};
+use std::io::BufRead;
+
+/// Call `f` with the 0-based index and content of each line of `reader`,
+/// without line terminators, reusing one buffer so that inputs of any size
+/// are read in memory bounded by their longest line.
+pub fn for_each_line(
+    mut reader: impl BufRead,
+    mut f: impl FnMut(usize, &str) -> Result<(), ParseError>,
+) -> Result<(), ParseError> {
+    let mut line = String::new();
+    for line_idx in 0.. {
+        line.clear();
+        let read = reader
+            .read_line(&mut line)
+            .map_err(|error| ParseError::Io {
+                message: error.to_string(),
+                at: Location::new(line_idx + 1, 1),
+            })?;
+        if read == 0 {
+            break;
+        }
+        let content = line.strip_suffix('\n').unwrap_or(&line);
+        f(line_idx, content.strip_suffix('\r').unwrap_or(content))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Read};
+
+    #[test]
+    fn test_for_each_line() {
+        let mut lines = vec![];
+        for_each_line("a b\r\n\nc".as_bytes(), |line_idx, line| {
+            lines.push((line_idx, line.to_string()));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                (0, "a b".to_string()),
+                (1, String::new()),
+                (2, "c".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_an_error() {
+        let reader = "ok\n".as_bytes().chain(&[0xff, b'\n'][..]);
+        let error = for_each_line(io::BufReader::new(reader), |_, _| Ok(())).unwrap_err();
+        assert!(matches!(
+            error,
+            ParseError::Io { at, .. } if at == Location::new(2, 1)
+        ));
+    }
+}
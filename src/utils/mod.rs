@@ -0,0 +1,12 @@
+pub mod ansi;
+pub mod direction;
+pub mod grid;
+pub mod lines;
+pub mod point;
+#[cfg(test)]
+pub mod rng;
+
+pub use direction::{Dir4, Dir8};
+pub use grid::Grid;
+pub use lines::for_each_line;
+pub use point::Point;
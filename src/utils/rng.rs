@@ -0,0 +1,21 @@
+/// Seeded xorshift64 generator for randomised tests, so that failures are
+/// reproducible without pulling in a random number crate.
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    /// The seed must not be zero, which xorshift never leaves.
+    pub fn new(seed: u64) -> XorShift {
+        assert_ne!(seed, 0, "xorshift needs a non-zero seed");
+        XorShift { state: seed }
+    }
+
+    /// Next number in `0..bound`
+    pub fn below(&mut self, bound: usize) -> usize {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state % bound as u64) as usize
+    }
+}